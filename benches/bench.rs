@@ -11,7 +11,7 @@ use mersenne_twister::{MT19937, MT19937_64};
 use rand::{Rng, SeedableRng, OsRng, StdRng, XorShiftRng};
 use rand::chacha::ChaChaRng;
 use rand::isaac::{IsaacRng, Isaac64Rng};
-use rand_split::Split;
+use rand_split::{Split, SplitRng, SplitPrf};
 use rand_split::siprng::SipRng;
 use rand_split::chaskeyrng::ChaskeyRng;
 use rand_split::twolcg::TwoLcgRng;
@@ -62,6 +62,142 @@ fn rand_split_isaac64(b: &mut Bencher) {
     b.bytes = size_of::<usize>() as u64 * RAND_BENCH_N;
 }
 
+#[bench]
+fn next_u64_siprng(b: &mut Bencher) {
+    let mut rng: SipRng = OsRng::new().unwrap().gen();
+    b.iter(|| {
+        for _ in 0..RAND_BENCH_N {
+            black_box(rng.next_u64());
+        }
+    });
+    b.bytes = size_of::<u64>() as u64 * RAND_BENCH_N;
+}
+
+/// `next_u32` hands out both halves of each 64-bit output, so it runs
+/// the SipHash finalization once per two calls.
+#[bench]
+fn next_u32_siprng(b: &mut Bencher) {
+    let mut rng: SipRng = OsRng::new().unwrap().gen();
+    b.iter(|| {
+        for _ in 0..RAND_BENCH_N {
+            black_box(rng.next_u32());
+        }
+    });
+    b.bytes = size_of::<u32>() as u64 * RAND_BENCH_N;
+}
+
+/// Truncating every 64-bit output, for comparison with
+/// `next_u32_siprng`.
+#[bench]
+fn next_u32_siprng_truncated(b: &mut Bencher) {
+    let mut rng: SipRng = OsRng::new().unwrap().gen();
+    b.iter(|| {
+        for _ in 0..RAND_BENCH_N {
+            black_box(rng.next_u64() as u32);
+        }
+    });
+    b.bytes = size_of::<u32>() as u64 * RAND_BENCH_N;
+}
+
+#[bench]
+fn next_u64_xorshift(b: &mut Bencher) {
+    let mut rng: XorShiftRng = OsRng::new().unwrap().gen();
+    b.iter(|| {
+        for _ in 0..RAND_BENCH_N {
+            black_box(rng.next_u64());
+        }
+    });
+    b.bytes = size_of::<u64>() as u64 * RAND_BENCH_N;
+}
+
+#[bench]
+fn next_u64_std(b: &mut Bencher) {
+    let mut rng = StdRng::new().unwrap();
+    b.iter(|| {
+        for _ in 0..RAND_BENCH_N {
+            black_box(rng.next_u64());
+        }
+    });
+    b.bytes = size_of::<u64>() as u64 * RAND_BENCH_N;
+}
+
+
+/*
+ * `fill_bytes` throughput with buffers of different sizes.
+ */
+
+macro_rules! fill_bytes_bench {
+    ($name: ident, $rng: ty, $len: expr) => {
+        #[bench]
+        fn $name(b: &mut Bencher) {
+            let mut rng: $rng = OsRng::new().unwrap().gen();
+            let mut buf = [0u8; $len];
+            b.iter(|| {
+                rng.fill_bytes(&mut buf);
+                black_box(&buf);
+            });
+            b.bytes = $len;
+        }
+    }
+}
+
+fill_bytes_bench!{fill_bytes_siprng_16, SipRng, 16}
+fill_bytes_bench!{fill_bytes_siprng_256, SipRng, 256}
+fill_bytes_bench!{fill_bytes_siprng_4096, SipRng, 4096}
+fill_bytes_bench!{fill_bytes_xorshift_16, XorShiftRng, 16}
+fill_bytes_bench!{fill_bytes_xorshift_256, XorShiftRng, 256}
+fill_bytes_bench!{fill_bytes_xorshift_4096, XorShiftRng, 4096}
+fill_bytes_bench!{fill_bytes_isaac64_4096, Isaac64Rng, 4096}
+fill_bytes_bench!{fill_bytes_chacha_4096, ChaChaRng, 4096}
+
+
+/*
+ * The cost of splitting, and of calling a PRF.
+ */
+
+#[bench]
+fn split_siprng(b: &mut Bencher) {
+    let mut rng: SipRng = OsRng::new().unwrap().gen();
+    b.iter(|| {
+        for _ in 0..RAND_BENCH_N {
+            black_box(rng.split());
+        }
+    });
+}
+
+#[bench]
+fn split_chaskeyrng(b: &mut Bencher) {
+    let mut rng: ChaskeyRng = OsRng::new().unwrap().gen();
+    b.iter(|| {
+        for _ in 0..RAND_BENCH_N {
+            black_box(rng.split());
+        }
+    });
+}
+
+#[bench]
+fn call_siprng(b: &mut Bencher) {
+    let mut rng: SipRng = OsRng::new().unwrap().gen();
+    let prf = rng.splitn();
+    b.iter(|| {
+        for i in 0..RAND_BENCH_N as u32 {
+            black_box(prf.call(i));
+        }
+    });
+}
+
+#[bench]
+fn call_chaskeyrng(b: &mut Bencher) {
+    let mut rng: ChaskeyRng = OsRng::new().unwrap().gen();
+    let prf = rng.splitn();
+    b.iter(|| {
+        for i in 0..RAND_BENCH_N as u32 {
+            black_box(prf.call(i));
+        }
+    });
+}
+
+
 /*
  * The following benches are lifted straight from the `rand` crate.
  * Having them here is just convenient.
@@ -0,0 +1,120 @@
+//! Drawing random numbers a few bits at a time.
+
+use rand::Rng;
+
+
+/// A wrapper around a generator that hands out its output a given
+/// number of bits at a time, keeping the bits it hasn't handed out yet
+/// for the next call.
+///
+/// The bits of each `next_u64` output are handed out from the least
+/// significant to the most significant, and the first bit handed out
+/// by `take_bits` is the least significant one of its result.  So
+/// taking 8 bits eight times gives the bytes of one output in
+/// little-endian order.
+pub struct BitReader<R> {
+    rng:   R,
+    buf:   u64,
+    avail: u32
+}
+
+impl<R: Rng> BitReader<R> {
+    /// Wrap `rng`.
+    pub fn new(rng: R) -> BitReader<R> {
+        BitReader {
+            rng,
+            buf:   0,
+            avail: 0
+        }
+    }
+
+    /// Return the next `n` bits in the low bits of a word, drawing
+    /// from the generator only when the buffered bits run out.
+    /// Panics unless `1 <= n <= 64`.
+    pub fn take_bits(&mut self, n: u32) -> u64 {
+        assert!((1..=64).contains(&n), "take_bits needs between 1 and 64 bits");
+        if n <= self.avail {
+            let bits = self.buf & mask(n);
+            self.buf = self.buf.checked_shr(n).unwrap_or(0);
+            self.avail -= n;
+            bits
+        } else {
+            let low = self.buf;
+            let have = self.avail;
+            let word = self.rng.next_u64();
+            let need = n - have;
+            self.buf = word.checked_shr(need).unwrap_or(0);
+            self.avail = 64 - need;
+            low | (word & mask(need)) << have
+        }
+    }
+
+    /// The number of bits buffered for the following calls.
+    pub fn buffered_bits(&self) -> u32 {
+        self.avail
+    }
+
+    /// Unwrap the generator, throwing away any buffered bits.
+    pub fn into_inner(self) -> R {
+        self.rng
+    }
+}
+
+/// The lowest `n` bits set, for `1 <= n <= 64`.
+#[inline]
+fn mask(n: u32) -> u64 {
+    u64::MAX >> (64 - n)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use siprng::SipRng;
+    use super::BitReader;
+
+
+    #[test]
+    fn test_take_bits_bytes() {
+        let mut bits = BitReader::new(SipRng::new(3, 4));
+        let mut words = SipRng::new(3, 4);
+        for _ in 0..100 {
+            let mut word = 0;
+            for i in 0..8 {
+                word |= bits.take_bits(8) << (8 * i);
+            }
+            assert_eq!(word, words.next_u64());
+            assert_eq!(bits.buffered_bits(), 0);
+        }
+    }
+
+    #[test]
+    fn test_take_bits_straddling() {
+        let sizes = [3, 64, 1, 17, 60, 64, 7, 32, 33, 2, 29];
+        let total: u32 = sizes.iter().sum();
+
+        let mut bits = BitReader::new(SipRng::new(5, 6));
+        let mut words = SipRng::new(5, 6);
+        let stream: Vec<u64> = (0..total.div_ceil(64)).map(|_| words.next_u64()).collect();
+        let bit_at = |i: u32| (stream[(i / 64) as usize] >> (i % 64)) & 1;
+
+        let mut pos = 0;
+        for &n in sizes.iter() {
+            let taken = bits.take_bits(n);
+            for j in 0..n {
+                assert_eq!((taken >> j) & 1, bit_at(pos + j));
+            }
+            if n < 64 {
+                assert_eq!(taken >> n, 0);
+            }
+            pos += n;
+        }
+        assert_eq!(bits.buffered_bits(), stream.len() as u32 * 64 - total);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_take_bits_zero() {
+        BitReader::new(SipRng::new(7, 8)).take_bits(0);
+    }
+}
@@ -0,0 +1,76 @@
+//! Deprecated shims for code written against the earlier consuming
+//! splitting API, where `splitn(self)` took the generator by value and
+//! the resulting splitter handed out generators through `branch(i)`.
+//! Enable the `compat` feature to use them, and migrate to
+//! `::SplitRng` and `::SplitPrf`.
+//!
+//! Don't import these traits alongside `::SplitRng`: both define a
+//! `splitn` method, so calls to it would be ambiguous.
+
+#![allow(deprecated)]
+
+use rand::Rng;
+use super::SplitPrf;
+
+
+/// A generator that is split by consuming it.
+#[deprecated(note = "use `rand_split::SplitRng`, whose `splitn` takes `&mut self`")]
+pub trait SplittableRng : Rng + Sized {
+    /// The splitter that `splitn` turns this generator into.
+    type Split : SplitRng<Self>;
+
+    /// Turn this generator into a splitter.
+    fn splitn(self) -> Self::Split;
+}
+
+/// A splitter that hands out generators by branch number.
+#[deprecated(note = "use `rand_split::SplitPrf`, whose `call` replaces `branch`")]
+pub trait SplitRng<R> {
+    /// Return the generator for branch `i`.
+    fn branch(&self, i: u32) -> R;
+}
+
+/// The splitter of every `::SplitRng`, wrapping its PRF.
+#[deprecated(note = "use the generator's `rand_split::SplitRng::Prf`")]
+pub struct Branches<R: ::SplitRng>(R::Prf);
+
+impl<R: ::SplitRng> SplittableRng for R {
+    type Split = Branches<R>;
+
+    fn splitn(mut self) -> Branches<R> {
+        Branches(::SplitRng::splitn(&mut self))
+    }
+}
+
+impl<R: ::SplitRng> SplitRng<R> for Branches<R> {
+    fn branch(&self, i: u32) -> R {
+        self.0.call(i)
+    }
+}
+
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use rand::Rng;
+    use siprng::SipRng;
+    use super::{SplittableRng, SplitRng};
+
+
+    fn old_style<R: SplittableRng>(rng: R) -> (R, R, R) {
+        let split = rng.splitn();
+        (split.branch(0), split.branch(1), split.branch(0))
+    }
+
+    #[test]
+    fn test_compat_splitn_branch() {
+        let (mut a, mut b, mut c) = old_style(SipRng::new(1, 2));
+        let x = a.next_u64();
+        assert!(x != b.next_u64());
+        assert_eq!(x, c.next_u64());
+
+        let mut rng = SipRng::new(1, 2);
+        let prf = ::SplitRng::splitn(&mut rng);
+        assert_eq!(x, ::SplitPrf::call(&prf, 0).next_u64());
+    }
+}
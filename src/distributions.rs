@@ -0,0 +1,223 @@
+//! Samplers for the distributions that the `SplitRng` methods
+//! `split_dirichlet`, `split_gen_exponential` and `split_gen_poisson`
+//! draw from.
+
+use rand::{Open01, Rng};
+use rand::distributions::{Gamma, IndependentSample};
+use std::f64::consts::PI;
+use super::{SplitRng, SplitPrf};
+
+
+/// Below this mean, `poisson` counts uniforms instead of using the
+/// PTRS rejection sampler.
+const POISSON_PTRS_THRESHOLD: f64 = 10.0;
+
+
+/// Draw a point of the probability simplex from the Dirichlet
+/// distribution with parameters `alpha`.  See
+/// `SplitRng::split_dirichlet`.
+pub fn dirichlet<R: SplitRng>(rng: &mut R, alpha: &[f64]) -> Vec<f64> {
+    assert!(alpha.iter().all(|a| a.is_finite() && *a > 0.0),
+            "Dirichlet parameters must be finite and positive");
+    let prf = rng.splitn();
+    let mut point: Vec<f64> = alpha.iter().enumerate().map(|(i, &a)| {
+        Gamma::new(a, 1.0).ind_sample(&mut prf.call(i as u32))
+    }).collect();
+
+    let sum: f64 = point.iter().sum();
+    if sum > 0.0 {
+        for x in point.iter_mut() {
+            *x /= sum;
+        }
+    } else if !point.is_empty() {
+        // Every draw underflowed, which takes parameters so small
+        // that the distribution is all but concentrated on the
+        // corners of the simplex.  Pick one, in proportion to the
+        // parameters, off a branch no component uses.
+        let corners: Vec<usize> = (0..alpha.len()).collect();
+        let corner = ::seq::choose_weighted(&mut prf.call(u32::MAX), &corners,
+                                            |&i| alpha[i]);
+        point[*corner.unwrap()] = 1.0;
+    }
+    point
+}
+
+
+/// Draw from the exponential distribution with rate `lambda`, by
+/// inversion.  Consumes exactly one `next_u64` word.
+pub fn exponential<R: Rng>(rng: &mut R, lambda: f64) -> f64 {
+    assert!(lambda.is_finite() && lambda > 0.0,
+            "exponential rate must be finite and positive");
+    let Open01(u) = rng.gen::<Open01<f64>>();
+    -u.ln() / lambda
+}
+
+/// Draw from the Poisson distribution with mean `lambda`.  Below
+/// `POISSON_PTRS_THRESHOLD` this multiplies uniforms until the product
+/// drops under `exp(-lambda)`, which consumes `k + 1` words to return
+/// `k`; above it, this uses Hörmann's PTRS transformed rejection
+/// sampler, which consumes two words per attempt.
+pub fn poisson<R: Rng>(rng: &mut R, lambda: f64) -> u64 {
+    assert!(lambda.is_finite() && lambda >= 0.0,
+            "Poisson mean must be finite and non-negative");
+    if lambda < POISSON_PTRS_THRESHOLD {
+        let limit = (-lambda).exp();
+        let mut product = 1.0;
+        let mut k = 0;
+        loop {
+            let Open01(u) = rng.gen::<Open01<f64>>();
+            product *= u;
+            if product <= limit {
+                return k;
+            }
+            k += 1;
+        }
+    }
+
+    // Hörmann, "The transformed rejection method for generating
+    // Poisson random variables", Insurance: Mathematics and Economics
+    // 12 (1993), with the constants of the paper.
+    let slam = lambda.sqrt();
+    let loglam = lambda.ln();
+    let b = 0.931 + 2.53 * slam;
+    let a = -0.059 + 0.02483 * b;
+    let invalpha = 1.1239 + 1.1328 / (b - 3.4);
+    let vr = 0.9277 - 3.6224 / (b - 2.0);
+    loop {
+        let u = rng.gen::<f64>() - 0.5;
+        let Open01(v) = rng.gen::<Open01<f64>>();
+        let us = 0.5 - u.abs();
+        let k = ((2.0 * a / us + b) * u + lambda + 0.43).floor();
+        if us >= 0.07 && v <= vr {
+            return k as u64;
+        }
+        if k < 0.0 || (us < 0.013 && v > us) {
+            continue;
+        }
+        if v.ln() + invalpha.ln() - (a / (us * us) + b).ln()
+            <= -lambda + k * loglam - log_gamma(k + 1.0)
+        {
+            return k as u64;
+        }
+    }
+}
+
+/// The logarithm of the gamma function for `x >= 1`, from Stirling's
+/// series, shifting small arguments up first.
+fn log_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 10] = [
+        8.333333333333333e-02, -2.777777777777778e-03,
+        7.936507936507937e-04, -5.952380952380952e-04,
+        8.417508417508418e-04, -1.917526917526918e-03,
+        6.41025641025641e-03, -2.955065359477124e-02,
+        1.796443723688307e-01, -1.3924322169059e+00,
+    ];
+    if x == 1.0 || x == 2.0 {
+        return 0.0;
+    }
+    let shift = if x <= 7.0 { (7.0 - x).floor() } else { 0.0 };
+    let mut x0 = x + shift;
+    let x2 = 1.0 / (x0 * x0);
+    let series = COEFFICIENTS.iter().rev().fold(0.0, |acc, c| acc * x2 + c);
+    let mut result = series / x0 + 0.5 * (2.0 * PI).ln() + (x0 - 0.5) * x0.ln() - x0;
+    for _ in 0..shift as u32 {
+        x0 -= 1.0;
+        result -= x0.ln();
+    }
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use siprng::SipRng;
+    use super::{dirichlet, exponential, log_gamma, poisson};
+
+
+    #[test]
+    fn test_dirichlet_simplex() {
+        let alpha = [0.5, 1.0, 2.0, 10.0, 0.1];
+        for seed in 0..100 {
+            let point = dirichlet(&mut SipRng::from_u64(seed), &alpha);
+            assert_eq!(point.len(), alpha.len());
+            assert!(point.iter().all(|&x| (0.0..=1.0).contains(&x)));
+            let sum: f64 = point.iter().sum();
+            assert!((sum - 1.0).abs() < 1e-9);
+            assert_eq!(point, dirichlet(&mut SipRng::from_u64(seed), &alpha));
+        }
+    }
+
+    #[test]
+    fn test_dirichlet_mean() {
+        // The mean of component i is alpha[i] / sum(alpha).
+        let alpha = [1.0, 2.0, 5.0];
+        let mut rng = SipRng::from_u64(0);
+        let mut mean = [0.0; 3];
+        let n = 10000;
+        for _ in 0..n {
+            for (m, x) in mean.iter_mut().zip(dirichlet(&mut rng, &alpha)) {
+                *m += x / n as f64;
+            }
+        }
+        for (m, a) in mean.iter().zip(&alpha) {
+            assert!((m - a / 8.0).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_dirichlet_tiny_parameters() {
+        let alpha = [1e-300; 4];
+        for seed in 0..20 {
+            let point = dirichlet(&mut SipRng::from_u64(seed), &alpha);
+            let sum: f64 = point.iter().sum();
+            assert!((sum - 1.0).abs() < 1e-9);
+        }
+        assert!(dirichlet(&mut SipRng::from_u64(0), &[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_dirichlet_zero_parameter() {
+        dirichlet(&mut SipRng::from_u64(0), &[1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_exponential_mean() {
+        let mut rng = SipRng::from_u64(0);
+        for &lambda in &[0.5, 1.0, 4.0] {
+            let n = 100_000;
+            let mean = (0..n).map(|_| exponential(&mut rng, lambda)).sum::<f64>() / n as f64;
+            assert!((mean * lambda - 1.0).abs() < 0.02);
+        }
+
+        let (mut a, mut b) = (SipRng::from_u64(1), SipRng::from_u64(1));
+        exponential(&mut a, 1.0);
+        b.next_u64();
+        assert_eq!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn test_poisson_mean_variance() {
+        let mut rng = SipRng::from_u64(0);
+        for &lambda in &[0.0, 0.5, 3.0, 9.5, 10.0, 42.0, 1000.0] {
+            let n = 100_000;
+            let draws: Vec<f64> = (0..n).map(|_| poisson(&mut rng, lambda) as f64).collect();
+            let mean = draws.iter().sum::<f64>() / n as f64;
+            let variance = draws.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n as f64;
+            let tolerance = 0.02 * lambda.max(1.0);
+            assert!((mean - lambda).abs() < tolerance);
+            assert!((variance - lambda).abs() < 3.0 * tolerance);
+        }
+    }
+
+    #[test]
+    fn test_log_gamma() {
+        let mut factorial = 1.0f64;
+        for k in 1..30 {
+            factorial *= k as f64;
+            assert!((log_gamma(k as f64 + 1.0) - factorial.ln()).abs() < 1e-10);
+        }
+    }
+
+}
@@ -0,0 +1,144 @@
+//! Cheap statistical sanity checks for the output of a generator.
+//!
+//! These are meant as **opt-in diagnostics** that catch catastrophic
+//! misconfiguration, like a generator stuck in an all-zero state.
+//! Passing them does not mean that a generator is any good, much less
+//! that it is cryptographically secure; the thresholds are set very
+//! loosely (six standard deviations) so that a sound generator fails
+//! them with negligible probability.
+
+use rand::Rng;
+use std::error::Error;
+use std::fmt;
+
+
+/// The number of 64-bit words that `health_check` examines.
+pub const WINDOW_WORDS: usize = 320;
+
+const WINDOW_BITS: u32 = 64 * WINDOW_WORDS as u32;
+
+/// How far (in bits) the monobit and runs statistics may stray from
+/// their expected values.  Both have a standard deviation of about
+/// `sqrt(WINDOW_BITS) / 2`, that is, 71.6 bits.
+const TOLERANCE: u32 = 430;
+
+
+/// The ways in which a generator can fail a `health_check`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthError {
+    /// The window had an implausible number of one bits.
+    Monobit { ones: u32 },
+    /// The window had an implausible number of runs of identical
+    /// bits.
+    Runs { runs: u32 },
+}
+
+impl fmt::Display for HealthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HealthError::Monobit { ones } =>
+                write!(f, "monobit test failed: {} of {} bits set",
+                       ones, WINDOW_BITS),
+            HealthError::Runs { runs } =>
+                write!(f, "runs test failed: {} runs in {} bits",
+                       runs, WINDOW_BITS),
+        }
+    }
+}
+
+impl Error for HealthError {}
+
+
+/// Draw `WINDOW_WORDS` outputs from `rng` and check that they don't
+/// look obviously non-random.  The bits of the window are read from
+/// each word's least significant bit to its most significant one.
+pub fn health_check<R: Rng>(rng: &mut R) -> Result<(), HealthError> {
+    let mut window = [0u64; WINDOW_WORDS];
+    for word in window.iter_mut() {
+        *word = rng.next_u64();
+    }
+    monobit(&window)?;
+    runs(&window)
+}
+
+/// The frequency test: about half of the bits should be set.
+fn monobit(window: &[u64]) -> Result<(), HealthError> {
+    let ones: u32 = window.iter().map(|w| w.count_ones()).sum();
+    if ones.abs_diff(WINDOW_BITS / 2) > TOLERANCE {
+        Err(HealthError::Monobit { ones })
+    } else {
+        Ok(())
+    }
+}
+
+/// The runs test: about half of all pairs of adjacent bits should
+/// differ.
+fn runs(window: &[u64]) -> Result<(), HealthError> {
+    let mut changes = 0;
+    let mut last: Option<u64> = None;
+    for &word in window {
+        changes += ((word ^ (word >> 1)) & (u64::MAX >> 1)).count_ones();
+        if let Some(prev) = last {
+            changes += ((prev >> 63) ^ (word & 1)) as u32;
+        }
+        last = Some(word);
+    }
+    let runs = changes + 1;
+    if runs.abs_diff(WINDOW_BITS / 2) > TOLERANCE {
+        Err(HealthError::Runs { runs })
+    } else {
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use rand::os::OsRng;
+    use siprng::SipRng;
+    use super::{health_check, HealthError};
+
+
+    /// A generator with an all-zero state that never changes.
+    struct ZeroRng;
+
+    impl Rng for ZeroRng {
+        fn next_u32(&mut self) -> u32 { 0 }
+    }
+
+    /// A generator whose output has balanced bits but far too many
+    /// runs.
+    struct AlternatingRng;
+
+    impl Rng for AlternatingRng {
+        fn next_u32(&mut self) -> u32 { 0x5555_5555 }
+        fn next_u64(&mut self) -> u64 { 0x5555_5555_5555_5555 }
+    }
+
+
+    #[test]
+    fn test_health_check_passes() {
+        let mut osrng = OsRng::new().expect("Could not create OsRng");
+        for _ in 0..10 {
+            let rng: SipRng = osrng.gen();
+            assert_eq!(rng.health_check(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_health_check_zero() {
+        match health_check(&mut ZeroRng) {
+            Err(HealthError::Monobit { ones: 0 }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_health_check_alternating() {
+        match health_check(&mut AlternatingRng) {
+            Err(HealthError::Runs { .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,890 @@
+//! `SplitRand` implementations for standard library types other than
+//! the primitive ones, and helpers to generate them with explicit
+//! parameters.
+
+use rand::{Open01, Rng};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, LinkedList, VecDeque};
+use std::f64::consts::PI;
+use std::iter::FromIterator;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::num::{NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroUsize};
+use std::num::{NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroIsize};
+use std::num::{Saturating, Wrapping};
+use std::ops::{Bound, RangeFrom, RangeTo};
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use super::{SplitRng, SplitPrf, SplitRand};
+
+
+/// The largest length of the collections generated by `SplitRand`
+/// impls.
+const MAX_LEN: usize = 32;
+
+/// The most components in a `PathBuf` generated by the `SplitRand`
+/// impl.
+const MAX_PATH_DEPTH: usize = 4;
+
+/// The longest component of the paths generated by
+/// `split_rand_path`.
+const MAX_PATH_COMPONENT_LEN: usize = 8;
+
+/// The largest `Duration` generated by the `SplitRand` impl: one day.
+const MAX_DURATION_SECS: u64 = 24 * 60 * 60;
+
+/// The window of the `Timestamp`s generated by the `SplitRand` impl:
+/// 2000-01-01 to 2100-01-01, UTC.
+const MIN_TIMESTAMP_MILLIS: u64 = 946_684_800_000;
+const MAX_TIMESTAMP_MILLIS: u64 = 4_102_444_800_000;
+
+
+/// Generates a string of up to 32 random `char`s.
+impl SplitRand for String {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> String {
+        split_rand_collection::<char, _, _>(rng)
+    }
+}
+
+/// A `char` that `SplitRand` draws uniformly from printable ASCII,
+/// space to tilde.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AsciiChar(pub char);
+
+/// A `char` that `SplitRand` draws uniformly from `[A-Za-z0-9]`, like
+/// `Rng::gen_ascii_chars` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AlphanumericChar(pub char);
+
+const ALPHANUMERIC: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+impl SplitRand for AsciiChar {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> AsciiChar {
+        AsciiChar(rng.gen_range(b' ', b'~' + 1) as char)
+    }
+}
+
+impl SplitRand for AlphanumericChar {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> AlphanumericChar {
+        AlphanumericChar(*rng.choose(ALPHANUMERIC).unwrap() as char)
+    }
+}
+
+
+/// A byte array that `SplitRand` fills with a single `fill_bytes`
+/// call on a new split, instead of generating each byte off its own
+/// split like the impl for `[u8; N]` does.  So the bytes are the ones
+/// `fill_bytes` would produce, which differ from the ones the
+/// element-by-element impl gives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ByteArray<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> SplitRand for ByteArray<N> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> ByteArray<N> {
+        let mut bytes = [0u8; N];
+        rng.split().fill_bytes(&mut bytes);
+        ByteArray(bytes)
+    }
+}
+
+/// A boolean array that `SplitRand` packs into as few words as it
+/// can: it is `SplitRng::split_gen_bools` on a new split, so element
+/// `i` is bit `i % 64` of word `i / 64` of the split, counting from the
+/// least significant bit, and the split gives `ceil(N / 64)` words.
+/// The impl for `[bool; N]` generates each element off its own split
+/// instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoolArray<const N: usize>(pub [bool; N]);
+
+impl<const N: usize> SplitRand for BoolArray<N> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> BoolArray<N> {
+        BoolArray(rng.split().split_gen_bools())
+    }
+}
+
+
+/// A number that `SplitRand` draws from the standard normal
+/// distribution, with mean 0 and standard deviation 1.
+///
+/// This uses the Box–Muller transform, so unlike the ziggurat method
+/// of `rand::distributions::StandardNormal` a draw always consumes
+/// exactly two `next_u64` words, and a caller can tell how far it
+/// advanced the generator.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct StandardNormal(pub f64);
+
+impl SplitRand for StandardNormal {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> StandardNormal {
+        let Open01(u) = rng.gen::<Open01<f64>>();
+        let v: f64 = rng.gen();
+        StandardNormal((-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos())
+    }
+}
+
+
+/// Generates a vector of up to 32 elements.
+impl<T: SplitRand> SplitRand for Vec<T> {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Vec<T> {
+        split_rand_collection(rng)
+    }
+}
+
+/// Generates a queue of up to 32 elements.
+impl<T: SplitRand> SplitRand for VecDeque<T> {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> VecDeque<T> {
+        split_rand_collection(rng)
+    }
+}
+
+/// Generates a list of up to 32 elements.
+impl<T: SplitRand> SplitRand for LinkedList<T> {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> LinkedList<T> {
+        split_rand_collection(rng)
+    }
+}
+
+/// Generates a heap of up to 32 elements.
+impl<T: SplitRand + Ord> SplitRand for BinaryHeap<T> {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> BinaryHeap<T> {
+        split_rand_collection(rng)
+    }
+}
+
+impl SplitRand for Cow<'static, str> {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Cow<'static, str> {
+        Cow::Owned(SplitRand::split_rand(rng))
+    }
+}
+
+/// Generates up to 32 bytes: the length off one split, and the bytes
+/// as by `split_rand_bytes`.  Unlike the impl for `Vec<u8>`, which
+/// generates each byte off its own split, this fills the bytes in
+/// one go.
+impl SplitRand for Cow<'static, [u8]> {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Cow<'static, [u8]> {
+        let len = rng.split().gen_range(0, MAX_LEN + 1);
+        Cow::Owned(split_rand_bytes(rng, len))
+    }
+}
+
+/// Generate `len` random bytes with a single `fill_bytes` call on a
+/// new split, so they come out in the same order as `fill_bytes` gives
+/// them, and a shorter blob is a prefix of a longer one.
+pub fn split_rand_bytes<R: SplitRng>(rng: &mut R, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rng.split().fill_bytes(&mut bytes);
+    bytes
+}
+
+
+/// Generates a `Duration` between zero and one day, inclusive.  Use
+/// `split_rand_duration` to pick a different bound.
+impl SplitRand for Duration {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Duration {
+        split_rand_duration(rng, Duration::from_secs(MAX_DURATION_SECS))
+    }
+}
+
+/// Generate a random `Duration` between zero and `max`, inclusive.
+/// The whole seconds and the nanoseconds are generated off separate
+/// branches.
+pub fn split_rand_duration<R: SplitRng>(rng: &mut R, max: Duration) -> Duration {
+    let secs = gen_up_to(&mut rng.split(), max.as_secs());
+    let max_nanos = if secs == max.as_secs() {
+        max.subsec_nanos()
+    } else {
+        999_999_999
+    };
+    let nanos = rng.split().gen_range(0, max_nanos + 1);
+    Duration::new(secs, nanos)
+}
+
+
+/// A point in time, in milliseconds since the Unix epoch.  Unlike
+/// `Instant` or `SystemTime`, any value can be constructed, so random
+/// timestamps are the same on every platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub u64);
+
+/// Generates a `Timestamp` between 2000 and 2100.  Use
+/// `split_rand_timestamp` to pick a different window.
+impl SplitRand for Timestamp {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Timestamp {
+        split_rand_timestamp(rng,
+                             Timestamp(MIN_TIMESTAMP_MILLIS),
+                             Timestamp(MAX_TIMESTAMP_MILLIS))
+    }
+}
+
+/// Generate a random `Timestamp` between `start` and `end`, inclusive,
+/// off a new split.  Panics if `start > end`.
+pub fn split_rand_timestamp<R: SplitRng>(rng: &mut R, start: Timestamp, end: Timestamp) -> Timestamp {
+    assert!(start <= end, "empty timestamp window");
+    Timestamp(start.0 + gen_up_to(&mut rng.split(), end.0 - start.0))
+}
+
+
+/// Generates a relative path of one to four components.  Use
+/// `split_rand_path` to pick the number of components.
+impl SplitRand for PathBuf {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> PathBuf {
+        let depth = rng.split().gen_range(1, MAX_PATH_DEPTH + 1);
+        split_rand_path(rng, depth)
+    }
+}
+
+/// Generate a random relative path of `depth` components, each of one
+/// to eight characters from `[A-Za-z0-9]`.  So the path is safe to
+/// create under any directory: it has no separators other than the
+/// platform's own, no `.` or `..` components and no characters that
+/// filesystems reserve.  Component `i` is generated off branch `i` of a
+/// PRF.
+pub fn split_rand_path<R: SplitRng>(rng: &mut R, depth: usize) -> PathBuf {
+    let prf = rng.splitn();
+    (0..depth).map(|i| {
+        let mut component = prf.call(i as u32);
+        let len = component.split().gen_range(1, MAX_PATH_COMPONENT_LEN + 1);
+        split_rand_elements::<AlphanumericChar, Vec<_>, _>(&mut component, len)
+            .into_iter()
+            .map(|AlphanumericChar(c)| c)
+            .collect::<String>()
+    }).collect()
+}
+
+
+impl<T: SplitRand> SplitRand for Wrapping<T> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Wrapping<T> {
+        Wrapping(SplitRand::split_rand(rng))
+    }
+}
+
+impl<T: SplitRand> SplitRand for Saturating<T> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Saturating<T> {
+        Saturating(SplitRand::split_rand(rng))
+    }
+}
+
+impl<T: SplitRand> SplitRand for Reverse<T> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Reverse<T> {
+        Reverse(SplitRand::split_rand(rng))
+    }
+}
+
+
+impl<T: SplitRand> SplitRand for Cell<T> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Cell<T> {
+        Cell::new(SplitRand::split_rand(rng))
+    }
+}
+
+impl<T: SplitRand> SplitRand for RefCell<T> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> RefCell<T> {
+        RefCell::new(SplitRand::split_rand(rng))
+    }
+}
+
+impl<T: SplitRand> SplitRand for Mutex<T> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Mutex<T> {
+        Mutex::new(SplitRand::split_rand(rng))
+    }
+}
+
+impl<T: SplitRand> SplitRand for RwLock<T> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> RwLock<T> {
+        RwLock::new(SplitRand::split_rand(rng))
+    }
+}
+
+
+/// Picks `Less`, `Equal` or `Greater` with equal probability.
+impl SplitRand for Ordering {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Ordering {
+        match rng.gen_range(0, 3) {
+            0 => Ordering::Less,
+            1 => Ordering::Equal,
+            _ => Ordering::Greater,
+        }
+    }
+}
+
+/// Picks `Some` or `None` with equal probability off one branch, and
+/// generates the payload off another, which is split off either way.
+/// So `Option<Box<dyn Fn(A) -> B>>` is an optional random function.
+impl<T: SplitRand> SplitRand for Option<T> {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Option<T> {
+        let some = rng.split().gen();
+        let mut payload = rng.split();
+        if some {
+            Some(payload.split_gen())
+        } else {
+            None
+        }
+    }
+}
+
+/// Picks `Ok` or `Err` with equal probability off one branch, and
+/// generates the payload off another.
+impl<T: SplitRand, E: SplitRand> SplitRand for Result<T, E> {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Result<T, E> {
+        if rng.split().gen() {
+            Ok(rng.split().split_gen())
+        } else {
+            Err(rng.split().split_gen())
+        }
+    }
+}
+
+
+impl<T: SplitRand> SplitRand for RangeTo<T> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> RangeTo<T> {
+        ..SplitRand::split_rand(rng)
+    }
+}
+
+impl<T: SplitRand> SplitRand for RangeFrom<T> {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> RangeFrom<T> {
+        SplitRand::split_rand(rng)..
+    }
+}
+
+/// Picks `Included`, `Excluded` or `Unbounded` with equal probability
+/// off one branch, and generates the payload off another, which is
+/// split off either way.  A pair of these is a random `RangeBounds`.
+impl<T: SplitRand> SplitRand for Bound<T> {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Bound<T> {
+        let variant = rng.split().gen_range(0, 3);
+        let mut payload = rng.split();
+        match variant {
+            0 => Bound::Included(payload.split_gen()),
+            1 => Bound::Excluded(payload.split_gen()),
+            _ => Bound::Unbounded,
+        }
+    }
+}
+
+
+impl SplitRand for Ipv4Addr {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Ipv4Addr {
+        Ipv4Addr::from(rng.next_u32())
+    }
+}
+
+impl SplitRand for Ipv6Addr {
+    #[inline]
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Ipv6Addr {
+        let (hi, lo) = (rng.next_u64(), rng.next_u64());
+        Ipv6Addr::from((u128::from(hi) << 64) | u128::from(lo))
+    }
+}
+
+/// Picks IPv4 or IPv6 with equal probability off one branch, and
+/// generates the address off another.
+impl SplitRand for IpAddr {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> IpAddr {
+        if rng.split().gen() {
+            IpAddr::V4(rng.split().split_gen())
+        } else {
+            IpAddr::V6(rng.split().split_gen())
+        }
+    }
+}
+
+
+/// Implements `SplitRand` for a `NonZero*` type by rejection sampling
+/// its base type, so that all non-zero values are equally likely.
+macro_rules! split_rand_nonzero_impl {
+    ($t:ident, $base:ident) => {
+        impl SplitRand for $t {
+            #[inline]
+            fn split_rand<R: SplitRng>(rng: &mut R) -> $t {
+                loop {
+                    if let Some(n) = $t::new(rng.gen::<$base>()) {
+                        return n;
+                    }
+                }
+            }
+        }
+    }
+}
+
+split_rand_nonzero_impl!{NonZeroIsize, isize}
+split_rand_nonzero_impl!{NonZeroI8, i8}
+split_rand_nonzero_impl!{NonZeroI16, i16}
+split_rand_nonzero_impl!{NonZeroI32, i32}
+split_rand_nonzero_impl!{NonZeroI64, i64}
+
+split_rand_nonzero_impl!{NonZeroUsize, usize}
+split_rand_nonzero_impl!{NonZeroU8, u8}
+split_rand_nonzero_impl!{NonZeroU16, u16}
+split_rand_nonzero_impl!{NonZeroU32, u32}
+split_rand_nonzero_impl!{NonZeroU64, u64}
+
+
+/// Generate a collection of up to `MAX_LEN` elements.  The length is
+/// drawn off one branch, and the elements are generated as by
+/// `split_rand_elements`.
+fn split_rand_collection<T, C, R>(rng: &mut R) -> C
+    where T: SplitRand,
+          C: FromIterator<T>,
+          R: SplitRng
+{
+    let len = rng.split().gen_range(0, MAX_LEN + 1);
+    split_rand_elements(rng, len)
+}
+
+/// Generate a collection of `len` elements, where element `i` is
+/// generated off index `i` of a PRF.  So each element depends on its
+/// position but not on how many others there are.
+fn split_rand_elements<T, C, R>(rng: &mut R, len: usize) -> C
+    where T: SplitRand,
+          C: FromIterator<T>,
+          R: SplitRng
+{
+    let prf = rng.splitn();
+    (0..len).map(|i| prf.call(i as u32).split_gen()).collect()
+}
+
+/// Generate a `u64` between zero and `max`, inclusive.
+fn gen_up_to<R: Rng>(rng: &mut R, max: u64) -> u64 {
+    if max == u64::MAX {
+        rng.gen()
+    } else {
+        rng.gen_range(0, max + 1)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::os::OsRng;
+    use siprng::SipRng;
+    use std::borrow::Cow;
+    use std::cell::{Cell, RefCell};
+    use std::cmp::{Ordering, Reverse};
+    use std::collections::{BinaryHeap, LinkedList, VecDeque};
+    use std::net::IpAddr;
+    use std::num::{NonZeroU32, NonZeroU8, Saturating, Wrapping};
+    use std::ops::{Bound, RangeBounds, RangeFrom, RangeTo};
+    use std::path::{Component, PathBuf};
+    use std::sync::{Mutex, RwLock};
+    use std::time::Duration;
+    use ::{SplitRng, SplitPrf, SplitRand};
+    use super::{split_rand_duration, split_rand_elements, split_rand_path};
+    use super::{split_rand_bytes, split_rand_timestamp};
+    use super::{AlphanumericChar, AsciiChar, BoolArray, ByteArray, StandardNormal, Timestamp};
+
+
+    fn gen_siprng() -> SipRng {
+        let mut osrng = OsRng::new().expect("Could not create OsRng");
+        osrng.gen()
+    }
+
+    #[test]
+    fn test_split_rand_duration() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        let max = Duration::new(2, 500_000_000);
+        for i in 0..1000 {
+            let a: Duration = prf.call(i).split_gen();
+            let b: Duration = prf.call(i).split_gen();
+            assert_eq!(a, b);
+            assert!(a.subsec_nanos() < 1_000_000_000);
+            assert!(a <= Duration::from_secs(24 * 60 * 60));
+
+            let c = split_rand_duration(&mut prf.call(i), max);
+            let d = split_rand_duration(&mut prf.call(i), max);
+            assert_eq!(c, d);
+            assert!(c <= max);
+        }
+    }
+
+    #[test]
+    fn test_split_rand_timestamp() {
+        let seed: (u64, u64) = gen_siprng().gen();
+        let a: Timestamp = SipRng::from_seed(seed).split_gen();
+        let b: Timestamp = SipRng::from_seed(seed).split_gen();
+        assert_eq!(a, b);
+        assert!(Timestamp(super::MIN_TIMESTAMP_MILLIS) <= a);
+        assert!(a <= Timestamp(super::MAX_TIMESTAMP_MILLIS));
+
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        let (start, end) = (Timestamp(1_500_000_000_000), Timestamp(1_500_000_000_009));
+        for i in 0..1000 {
+            let c = split_rand_timestamp(&mut prf.call(i), start, end);
+            assert_eq!(c, split_rand_timestamp(&mut prf.call(i), start, end));
+            assert!(start <= c && c <= end);
+        }
+        assert_eq!(split_rand_timestamp(&mut rng, end, end), end);
+        split_rand_timestamp(&mut rng, Timestamp(0), Timestamp(u64::MAX));
+    }
+
+    #[test]
+    fn test_split_rand_restricted_chars() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        for i in 0..10_000 {
+            let AlphanumericChar(a) = prf.call(i).split_gen();
+            let AlphanumericChar(b) = prf.call(i).split_gen();
+            assert_eq!(a, b);
+            assert!(a.is_ascii_alphanumeric());
+
+            let AsciiChar(c) = prf.call(i).split_gen();
+            assert!(c.is_ascii() && !c.is_ascii_control());
+        }
+    }
+
+    #[test]
+    fn test_split_rand_path() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        for i in 0..1000 {
+            let a: PathBuf = prf.call(i).split_gen();
+            let b: PathBuf = prf.call(i).split_gen();
+            assert_eq!(a, b);
+            assert!(a.is_relative());
+            let count = a.components().count();
+            assert!((1..=super::MAX_PATH_DEPTH).contains(&count));
+            for component in a.components() {
+                match component {
+                    Component::Normal(name) => {
+                        let name = name.to_str().unwrap();
+                        assert!(name.chars().all(|c| c.is_ascii_alphanumeric()));
+                    }
+                    other => panic!("unexpected component {:?}", other),
+                }
+            }
+
+            let c = split_rand_path(&mut prf.call(i), 10);
+            assert_eq!(c.components().count(), 10);
+        }
+    }
+
+    #[test]
+    fn test_split_rand_byte_array() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        for i in 0..100 {
+            let ByteArray(a) = prf.call(i).split_gen::<ByteArray<37>>();
+            let ByteArray(b) = prf.call(i).split_gen::<ByteArray<37>>();
+            assert_eq!(a[..], b[..]);
+
+            let mut c = [0u8; 37];
+            prf.call(i).split().fill_bytes(&mut c);
+            assert_eq!(a[..], c[..]);
+        }
+        let ByteArray(empty) = rng.split_gen::<ByteArray<0>>();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_split_rand_bytes() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        for i in 0..100 {
+            let blob = split_rand_bytes(&mut prf.call(i), 1000);
+            assert_eq!(blob.len(), 1000);
+            assert_eq!(blob, split_rand_bytes(&mut prf.call(i), 1000));
+            assert_eq!(split_rand_bytes(&mut prf.call(i), 13)[..], blob[..13]);
+
+            let a: Cow<'static, [u8]> = prf.call(i).split_gen();
+            let b: Cow<'static, [u8]> = prf.call(i).split_gen();
+            assert_eq!(a, b);
+            assert!(a.len() <= super::MAX_LEN);
+            let mut r = prf.call(i);
+            r.split();
+            assert_eq!(a[..], split_rand_bytes(&mut r, a.len())[..]);
+        }
+        assert!(split_rand_bytes(&mut rng, 0).is_empty());
+    }
+
+    #[test]
+    fn test_split_rand_bool_array() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        for i in 0..100 {
+            let BoolArray(a) = prf.call(i).split_gen::<BoolArray<20>>();
+            let BoolArray(b) = prf.call(i).split_gen::<BoolArray<20>>();
+            assert_eq!(a, b);
+
+            let mut split = prf.call(i).split();
+            let word = split.next_u64();
+            for (j, &bit) in a.iter().enumerate() {
+                assert_eq!(bit, (word >> j) & 1 == 1);
+            }
+
+            // One word for 20 elements, three for 130.
+            let mut words = prf.call(i).split();
+            let BoolArray(c) = prf.call(i).split_gen::<BoolArray<130>>();
+            let expected: Vec<u64> = (0..3).map(|_| words.next_u64()).collect();
+            for (j, &bit) in c.iter().enumerate() {
+                assert_eq!(bit, (expected[j / 64] >> (j % 64)) & 1 == 1);
+            }
+        }
+        let BoolArray(empty) = rng.split_gen::<BoolArray<0>>();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_split_rand_standard_normal() {
+        let mut rng = SipRng::from_u64(0);
+        let n = 1_000_000;
+        let (mut sum, mut sum_sq) = (0.0, 0.0);
+        for _ in 0..n {
+            let StandardNormal(z) = rng.split_gen();
+            sum += z;
+            sum_sq += z * z;
+        }
+        let mean = sum / n as f64;
+        let variance = sum_sq / n as f64 - mean * mean;
+        assert!(mean.abs() < 0.01);
+        assert!((variance - 1.0).abs() < 0.01);
+
+        // Exactly two words per draw.
+        let (mut a, mut b) = (SipRng::from_u64(1), SipRng::from_u64(1));
+        let _: StandardNormal = a.split_gen();
+        b.next_u64();
+        b.next_u64();
+        assert_eq!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn test_split_rand_nonzero() {
+        let mut rng = gen_siprng();
+        for _ in 0..1_000_000 {
+            let n: NonZeroU32 = rng.split_gen();
+            assert!(n.get() != 0);
+        }
+        for _ in 0..10_000 {
+            let n: NonZeroU8 = rng.split_gen();
+            assert!(n.get() != 0);
+        }
+    }
+
+    #[test]
+    fn test_split_rand_wrapping() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        for i in 0..100 {
+            let a: [Wrapping<u8>; 16] = prf.call(i).split_gen();
+            let b: [Wrapping<u8>; 16] = prf.call(i).split_gen();
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn test_split_rand_partial_ranges() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        for i in 0..100 {
+            let a: RangeTo<u32> = prf.call(i).split_gen();
+            let b: RangeTo<u32> = prf.call(i).split_gen();
+            let c: RangeFrom<u32> = prf.call(i).split_gen();
+            assert_eq!(a, b);
+            assert_eq!(a.end, prf.call(i).split_gen::<u32>());
+            assert_eq!(c.start, a.end);
+        }
+    }
+
+    #[test]
+    fn test_split_rand_bound() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        let mut counts = [0; 3];
+        for i in 0..3000 {
+            let a: Bound<u32> = prf.call(i).split_gen();
+            let b: Bound<u32> = prf.call(i).split_gen();
+            assert_eq!(a, b);
+            counts[match a {
+                Bound::Included(_) => 0,
+                Bound::Excluded(_) => 1,
+                Bound::Unbounded => 2,
+            }] += 1;
+
+            // The payload doesn't depend on the variant.
+            let mut payload = prf.call(i);
+            payload.split();
+            let x: u32 = payload.split().split_gen();
+            match a {
+                Bound::Included(y) | Bound::Excluded(y) => assert_eq!(x, y),
+                Bound::Unbounded => {}
+            }
+
+            let range: (Bound<u8>, Bound<u8>) = prf.call(i).split_gen();
+            let _ = range.contains(&7);
+        }
+        assert!(counts.iter().all(|&c| 900 < c && c < 1100));
+    }
+
+    #[test]
+    fn test_split_rand_optional_function() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        let mut some = 0;
+        for i in 0..1000 {
+            let f: Option<Box<dyn Fn(u32) -> u64>> = prf.call(i).split_gen();
+            let g: Option<Box<dyn Fn(u32) -> u64>> = prf.call(i).split_gen();
+            assert_eq!(f.is_some(), g.is_some());
+            if let (Some(f), Some(g)) = (f, g) {
+                assert!((0..100).all(|x| f(x) == g(x) && f(x) == f(x)));
+                assert!(f(0) != f(1));
+                some += 1;
+            }
+
+            // The generator advances the same whether it's `Some` or
+            // `None`.
+            let (mut a, mut b) = (prf.call(i), prf.call(i));
+            let _: Option<u8> = a.split_gen();
+            let _: Option<[u64; 4]> = b.split_gen();
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert!((400..600).contains(&some));
+    }
+
+    #[test]
+    fn test_split_rand_ordering() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        let mut counts = [0; 3];
+        for i in 0..100_000 {
+            let a: Ordering = prf.call(i).split_gen();
+            let b: Ordering = prf.call(i).split_gen();
+            assert_eq!(a, b);
+            counts[(a as i8 + 1) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| 32_000 < c && c < 34_700));
+    }
+
+    #[test]
+    fn test_split_rand_ip_addr() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        let mut v4 = 0;
+        for i in 0..10_000 {
+            let a: IpAddr = prf.call(i).split_gen();
+            let b: IpAddr = prf.call(i).split_gen();
+            assert_eq!(a, b);
+            if a.is_ipv4() {
+                v4 += 1;
+            }
+        }
+        assert!(4_500 < v4 && v4 < 5_500);
+    }
+
+    #[test]
+    fn test_split_rand_collections() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        for i in 0..100 {
+            let a: Vec<u16> = prf.call(i).split_gen();
+            let b: VecDeque<u16> = prf.call(i).split_gen();
+            let c: LinkedList<u16> = prf.call(i).split_gen();
+            assert!(a.len() <= super::MAX_LEN);
+            assert!(a.iter().eq(b.iter()));
+            assert!(a.iter().eq(c.iter()));
+
+            // The same elements, in heap order.
+            let heap: BinaryHeap<u16> = prf.call(i).split_gen();
+            assert_eq!(heap.peek(), a.iter().max());
+            let mut sorted = a.clone();
+            sorted.sort();
+            assert_eq!(heap.into_sorted_vec(), sorted);
+        }
+    }
+
+    #[test]
+    fn test_split_rand_elements_positional() {
+        let seed: (u64, u64) = gen_siprng().gen();
+        let short: VecDeque<u16> =
+            split_rand_elements(&mut SipRng::from_seed(seed), 3);
+        for len in 3..40 {
+            let long: VecDeque<u16> =
+                split_rand_elements(&mut SipRng::from_seed(seed), len);
+            assert_eq!(long[2], short[2]);
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Config {
+        name:     Cow<'static, str>,
+        retries:  Saturating<u8>,
+        priority: Reverse<u32>,
+    }
+
+    impl SplitRand for Config {
+        fn split_rand<R: SplitRng>(rng: &mut R) -> Config {
+            let (name, retries, priority) = rng.split_gen();
+            Config {
+                name,
+                retries,
+                priority,
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_rand_std_wrappers() {
+        let mut rng = gen_siprng();
+        let prf = rng.splitn();
+        for i in 0..100 {
+            let a: Config = prf.call(i).split_gen();
+            let b: Config = prf.call(i).split_gen();
+            assert_eq!(a, b);
+            assert!(a.name.chars().count() <= super::MAX_LEN);
+        }
+    }
+
+    struct Cache {
+        hits:    RefCell<u32>,
+        last:    Cell<u64>,
+        shared:  Mutex<u16>,
+        readers: RwLock<bool>,
+    }
+
+    impl SplitRand for Cache {
+        fn split_rand<R: SplitRng>(rng: &mut R) -> Cache {
+            split_rand!(rng => Cache {
+                hits:    RefCell<u32> @ 0,
+                last:    Cell<u64> @ 1,
+                shared:  Mutex<u16> @ 2,
+                readers: RwLock<bool> @ 3,
+            })
+        }
+    }
+
+    #[test]
+    fn test_split_rand_interior_mutability() {
+        let seed: (u64, u64) = gen_siprng().gen();
+        let a: Cache = SipRng::from_seed(seed).split_gen();
+        let b: Cache = SipRng::from_seed(seed).split_gen();
+        assert_eq!(a.hits, b.hits);
+        assert_eq!(a.last, b.last);
+        assert_eq!(*a.shared.lock().unwrap(), *b.shared.lock().unwrap());
+        assert_eq!(*a.readers.read().unwrap(), *b.readers.read().unwrap());
+
+        // The wrappers add no splits of their own.
+        let hits: u32 = SipRng::from_seed(seed).splitn().call(0).split_gen();
+        assert_eq!(*a.hits.borrow(), hits);
+    }
+
+}
@@ -195,16 +195,42 @@
 
 
 extern crate rand;
+#[cfg(feature = "rand-upgrade")]
+extern crate rand_core;
 
+#[macro_use]
+mod macros;
 pub mod generic;
 pub mod siprng;
 pub mod chaskeyrng;
 pub mod twolcg;
+pub mod bits;
+pub mod health;
+pub mod range;
+pub mod pattern;
+pub mod registry;
+pub mod prelude;
+#[cfg(feature = "compat")]
+pub mod compat;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+mod impls;
+mod distributions;
+mod seq;
+
+pub use impls::{split_rand_bytes, split_rand_duration, split_rand_path, split_rand_timestamp};
+pub use impls::{AsciiChar, AlphanumericChar, BoolArray, ByteArray, StandardNormal, Timestamp};
+pub use siprng::split_thread_rng;
 
 use rand::{Rng, Rand};
 use chaskeyrng::{ChaskeyRng, ChaskeyPrf};
+use std::collections::{HashMap, HashSet};
 #[allow(deprecated)]
-use std::hash::{Hash, Hasher, SipHasher};
+use std::hash::{BuildHasher, Hash, Hasher, SipHasher};
+use std::marker::PhantomData;
+use std::num::Saturating;
+use pattern::{Pattern, PatternError};
+use range::{SampleInclusive, SplitSampleRange};
 
 
 /// A wrapper that generically adds splittability to RNGs.
@@ -235,9 +261,544 @@ pub trait SplitRng : Rng + Sized + 'static {
     /// Split a second RNG off this one.
     fn split(&mut self) -> Self;
     
+    /// Split a PRF off this generator whose branches are addressed
+    /// by labels of any `Hash` type, such as strings, instead of by
+    /// `u32` indices.  This is how generic code names subsystems;
+    /// `SipRng::fork_labeled` is the `SipRng`-specific counterpart,
+    /// which absorbs the label whole.
+    fn splitn_labeled(&mut self) -> LabeledPrf<Self> {
+        LabeledPrf(HashPrf::new(self))
+    }
+
+    /// Generate a random value of a `SplitRand` type.  This is the
+    /// splittable counterpart of `Rng::gen`: `gen` works for `Rand`
+    /// types and draws everything from this generator's stream, while
+    /// `split_gen` works for `SplitRand` types, which generate their
+    /// components off splits.  For the primitive types both give the
+    /// same results.  The free function `split_rand` does the same.
     fn split_gen<A: SplitRand>(&mut self) -> A {
         SplitRand::split_rand::<Self>(self)
     }
+
+    /// Generate a random value of a `SplitRand` type and return `f`
+    /// applied to it, for deriving a distribution from an existing
+    /// one without writing a new `SplitRand` impl.
+    fn split_map_gen<T: SplitRand, U, F: FnOnce(T) -> U>(&mut self, f: F) -> U {
+        f(self.split_gen())
+    }
+
+    /// Generate a function like the `SplitRand` impl for
+    /// `Box<dyn Fn(A) -> B>` does, but one that can be shared between
+    /// threads.  The closure only captures a PRF split off this
+    /// generator, so this requires that PRF to be `Send` and `Sync`.
+    fn split_gen_sync_fn<A, B>(&mut self) -> Box<dyn Fn(A) -> B + Send + Sync>
+        where A: Hash,
+              B: Rand,
+              Self::Prf: Send + Sync
+    {
+        let prf = HashPrf::new(self);
+        Box::new(move |arg: A| {
+            Rand::rand(&mut prf.call::<A, Self>(&arg))
+        })
+    }
+
+    /// Generate a random value of a `SplitRand` type that satisfies
+    /// `pred`, by rejection: attempt `i` is generated off branch `i` of
+    /// a PRF split off this generator, so this generator advances by
+    /// one split however many attempts are made.  Returns `None` if
+    /// none of the first `max_attempts` satisfies `pred`.
+    fn split_filter_gen<T, F>(&mut self, max_attempts: u32, pred: F) -> Option<T>
+        where T: SplitRand,
+              F: Fn(&T) -> bool
+    {
+        let prf = self.splitn();
+        (0..max_attempts)
+            .map(|i| prf.call(i).split_gen())
+            .find(|value| pred(value))
+    }
+
+    /// Draw a number uniformly from `[low, high]`, for exercising
+    /// saturating arithmetic near given bounds.  Panics if `low >
+    /// high`.
+    fn split_gen_saturating<T: SampleInclusive>(&mut self, low: T, high: T) -> Saturating<T> {
+        Saturating(T::sample_inclusive(self, low, high))
+    }
+
+    /// Draw a number from the normal distribution with the given mean
+    /// and standard deviation, off a new split of this generator.  The
+    /// draw is a `StandardNormal`, so it consumes exactly two
+    /// `next_u64` words of the split, and this generator advances by
+    /// the one split only.  Panics if `std` is negative or not finite.
+    fn split_gen_normal(&mut self, mean: f64, std: f64) -> f64 {
+        assert!(std.is_finite() && std >= 0.0,
+                "standard deviation must be finite and non-negative");
+        let StandardNormal(z) = self.split().split_gen();
+        mean + std * z
+    }
+
+    /// Draw from the exponential distribution with rate `lambda`, and
+    /// so mean `1 / lambda`, off a new split of this generator.  The
+    /// draw consumes exactly one `next_u64` word of the split.  Panics
+    /// unless `lambda` is finite and positive.
+    fn split_gen_exponential(&mut self, lambda: f64) -> f64 {
+        distributions::exponential(&mut self.split(), lambda)
+    }
+
+    /// Draw from the Poisson distribution with mean `lambda`, off a
+    /// new split of this generator.  For `lambda` under 10 a draw of
+    /// `k` consumes `k + 1` `next_u64` words of the split; for larger
+    /// means it uses a rejection sampler that consumes two words per
+    /// attempt.  Either way this generator advances by the one split
+    /// only.  Panics unless `lambda` is finite and non-negative.
+    fn split_gen_poisson(&mut self, lambda: f64) -> u64 {
+        distributions::poisson(&mut self.split(), lambda)
+    }
+
+    /// Generate a string that matches `pattern`, in the small pattern
+    /// language of the `pattern` module, or return an error if the
+    /// pattern doesn't parse.  See `pattern::Pattern::generate` for
+    /// how the string depends on the branches of this generator; to
+    /// generate many strings, parse the pattern once instead.
+    fn split_gen_string_matching(&mut self, pattern: &str) -> Result<String, PatternError> {
+        Pattern::parse(pattern).map(|pattern| pattern.generate(self))
+    }
+
+    /// Draw a number uniformly from `range`, which may be half-open,
+    /// like `0..10` or `0.0..1.0`, or inclusive, like `0..=10`.
+    /// Integers are drawn without bias; inclusive ranges may end at
+    /// the type's maximum.  This draws from this generator directly,
+    /// like `Rng::gen_range` does.  Panics if the range is empty, or
+    /// if a floating-point range is infinitely wide.
+    fn split_gen_within<T, Q: SplitSampleRange<T>>(&mut self, range: Q) -> T {
+        range.sample_split(self)
+    }
+
+    /// Generate the 16 bytes of a version 4 UUID, as in RFC 4122: 122
+    /// random bits, filled in by a single `fill_bytes` call on a new
+    /// split of this generator, with the version and variant bits
+    /// set.  So this generator advances by the one split only.
+    fn split_gen_uuid(&mut self) -> [u8; 16] {
+        let ByteArray(mut uuid) = self.split_gen::<ByteArray<16>>();
+        uuid[6] = (uuid[6] & 0x0f) | 0x40;
+        uuid[8] = (uuid[8] & 0x3f) | 0x80;
+        uuid
+    }
+
+    /// Generate `count` strictly increasing timestamps, the first
+    /// `start` and each of the others the one before plus a gap drawn
+    /// uniformly from `[1, max_gap]`.  Gap `i` is drawn off branch `i`
+    /// of a PRF split off this generator, so a shorter sequence is a
+    /// prefix of a longer one.
+    ///
+    /// # Panics
+    ///
+    /// If `max_gap` is zero, if `count` exceeds `u32::MAX + 1`, or if
+    /// the timestamps overflow.
+    fn split_gen_timestamps(&mut self, start: u64, count: usize, max_gap: u64) -> Vec<u64> {
+        assert!(max_gap > 0, "maximum gap must be positive");
+        assert!(count as u64 <= u32::MAX as u64 + 1, "more timestamps than PRF branches");
+        let prf = self.splitn();
+        let mut timestamps = Vec::with_capacity(count);
+        let mut t = start;
+        for i in 0..count {
+            if i > 0 {
+                let gap = u64::sample_inclusive(&mut prf.call(i as u32), 1, max_gap);
+                t = t.checked_add(gap).expect("timestamp overflow");
+            }
+            timestamps.push(t);
+        }
+        timestamps
+    }
+
+    /// Generate an array of random booleans, 64 to a `next_u64` word
+    /// drawn from this generator.  Element `i` is bit `i % 64` of word
+    /// `i / 64`, counting from the least significant bit, so only the
+    /// last word may be partly wasted.
+    fn split_gen_bools<const N: usize>(&mut self) -> [bool; N] {
+        let mut bools = [false; N];
+        for chunk in bools.chunks_mut(64) {
+            let word = self.next_u64();
+            for (i, b) in chunk.iter_mut().enumerate() {
+                *b = (word >> i) & 1 == 1;
+            }
+        }
+        bools
+    }
+
+    /// Generate a random set of `n_bits` bits, packed 64 to a word:
+    /// bit `i` is bit `i % 64` of word `i / 64`, counting from the
+    /// least significant bit, in the same order as `split_gen_bools`
+    /// and `BoolArray`.  The words are successive `next_u64` outputs of
+    /// a new split of this generator, and the bits of the last word
+    /// beyond `n_bits` are zero.
+    fn split_gen_bitset(&mut self, n_bits: usize) -> Vec<u64> {
+        let mut split = self.split();
+        let mut words: Vec<u64> = (0..n_bits.div_ceil(64)).map(|_| split.next_u64()).collect();
+        if !n_bits.is_multiple_of(64) {
+            if let Some(last) = words.last_mut() {
+                *last &= (1 << (n_bits % 64)) - 1;
+            }
+        }
+        words
+    }
+
+    /// Return an endless iterator of random values, each generated off
+    /// a new split of this generator.  This is the splittable analogue
+    /// of `Rng::gen_iter`: no item depends on how much randomness the
+    /// ones before it consumed.
+    fn split_sample_iter<'a, T: SplitRand>(&'a mut self) -> SplitGenerator<'a, T, Self> {
+        SplitGenerator {
+            rng: self,
+            _marker: PhantomData
+        }
+    }
+
+    /// Split two generators off this one, meant for the two halves of
+    /// a divide-and-conquer computation, such as the two closures
+    /// passed to `rayon::join`.  If each half splits its own generator
+    /// again at the next recursion step, the results are the same no
+    /// matter how the work ends up being scheduled:
+    ///
+    /// ```
+    /// extern crate rand;
+    /// extern crate rand_split;
+    ///
+    /// use rand::{Rng, thread_rng};
+    /// use rand_split::SplitRng;
+    /// use rand_split::siprng::SipRng;
+    /// use std::thread;
+    ///
+    /// fn sum(rng: &mut SipRng, depth: u32) -> u64 {
+    ///     if depth == 0 {
+    ///         return rng.gen::<u32>() as u64;
+    ///     }
+    ///     let (mut left, mut right) = rng.split2();
+    ///     let handle = thread::spawn(move || sum(&mut left, depth - 1));
+    ///     let b = sum(&mut right, depth - 1);
+    ///     handle.join().unwrap() + b
+    /// }
+    ///
+    /// # fn main() {
+    /// let seed: (u64, u64) = thread_rng().gen();
+    /// let a = sum(&mut SipRng::new(seed.0, seed.1), 4);
+    /// let b = sum(&mut SipRng::new(seed.0, seed.1), 4);
+    /// assert_eq!(a, b);
+    /// # }
+    /// ```
+    fn split2(&mut self) -> (Self, Self) {
+        let prf = self.splitn();
+        (prf.call(0), prf.call(1))
+    }
+
+    /// Return two streams for paired sequences, such as requests and
+    /// their responses.  This is `split2` under a name that says what
+    /// it is for: the two streams are branches 0 and 1 of a PRF, so
+    /// the `k`th value drawn from one is independent of the `k`th,
+    /// or any, value drawn from the other, and the pair is
+    /// reproducible from this generator's state.
+    fn zip_streams(&mut self) -> (Self, Self) {
+        self.split2()
+    }
+
+    /// Return an endless iterator of pairs, each `A` generated off a
+    /// new split of the first stream of `zip_streams` and each `B` off
+    /// a new split of the second.  So the `A`s and the `B`s are
+    /// independent, and neither depends on how much randomness the
+    /// other consumes.
+    fn zip_gen<A: SplitRand, B: SplitRand>(&mut self) -> ZipGenerator<A, B, Self> {
+        let (left, right) = self.zip_streams();
+        ZipGenerator {
+            left,
+            right,
+            _marker: PhantomData
+        }
+    }
+
+    /// Generate `n` random values, value `i` off branch `i` of a PRF
+    /// split off this generator.  So each value depends on its
+    /// position but not on `n`, and this generator advances by one
+    /// split whatever `n` is.  Panics if `n` exceeds `u32::MAX + 1`,
+    /// the number of branches.
+    fn split_many<T: SplitRand>(&mut self, n: usize) -> Vec<T> {
+        let mut values = Vec::with_capacity(n);
+        self.fill(&mut values, n);
+        values
+    }
+
+    /// Append `n` random values to `out`, generated like `split_many`
+    /// generates them, but without allocating a new vector.  Panics
+    /// if `n` exceeds `u32::MAX + 1`.
+    fn fill<T: SplitRand>(&mut self, out: &mut Vec<T>, n: usize) {
+        assert!(n as u64 <= u32::MAX as u64 + 1, "more values than PRF branches");
+        let prf = self.splitn();
+        out.extend((0..n).map(|i| prf.call(i as u32).split_gen::<T>()));
+    }
+
+    /// Fold `f` over `n` random values, generated like `split_many`
+    /// generates them, but without collecting them.  Panics if `n`
+    /// exceeds `u32::MAX + 1`.
+    fn split_fold<T, A, F>(&mut self, n: usize, init: A, f: F) -> A
+        where T: SplitRand,
+              F: Fn(A, T) -> A
+    {
+        assert!(n as u64 <= u32::MAX as u64 + 1, "more values than PRF branches");
+        let prf = self.splitn();
+        (0..n).fold(init, |acc, i| f(acc, prf.call(i as u32).split_gen()))
+    }
+
+    /// Generate a `rows` by `cols` grid of random values, the cell in
+    /// row `r` and column `c` off branch `r * cols + c` of a PRF split
+    /// off this generator.  So a cell depends on its position and on
+    /// `cols`, but not on `rows`, and this generator advances by one
+    /// split whatever the dimensions are.  Panics if there are more
+    /// cells than PRF branches.
+    fn split_grid<T: SplitRand>(&mut self, rows: usize, cols: usize) -> Vec<Vec<T>> {
+        let cells = (rows as u64).checked_mul(cols as u64);
+        assert!(cells.is_some_and(|n| n <= u32::MAX as u64 + 1), "more cells than PRF branches");
+        let prf = self.splitn();
+        (0..rows).map(|r| {
+            (0..cols).map(|c| prf.call((r * cols + c) as u32).split_gen()).collect()
+        }).collect()
+    }
+
+    /// Generate a map that assigns a random value to each of the
+    /// given keys.  Each value is generated off a branch selected by
+    /// hashing its key, so it depends neither on the order in which
+    /// the keys are supplied nor on what other keys are present.
+    fn split_map<K, V, I>(&mut self, keys: I) -> HashMap<K, V>
+        where K: Hash + Eq,
+              V: SplitRand,
+              I: IntoIterator<Item=K>
+    {
+        let prf = HashPrf::new(self);
+        keys.into_iter().map(|key| {
+            let value = prf.call::<K, Self>(&key).split_gen();
+            (key, value)
+        }).collect()
+    }
+
+    /// Generate a map of exactly `n` entries.  Keys are generated off
+    /// successive branches of a PRF split off this generator, skipping
+    /// duplicates, until there are `n` distinct ones; the values are
+    /// then generated as by `split_map`, so each depends only on its
+    /// key.
+    ///
+    /// # Panics
+    ///
+    /// If `32 * n + 64` keys are generated without `n` of them being
+    /// distinct, which for keys drawn uniformly from a domain of at
+    /// least `n` values is all but impossible, and otherwise means `K`
+    /// has too few values.
+    fn split_map_exact<K, V>(&mut self, n: usize) -> HashMap<K, V>
+        where K: SplitRand + Hash + Eq,
+              V: SplitRand
+    {
+        let max_attempts = n.saturating_mul(32).saturating_add(64);
+        let prf = self.splitn();
+        let mut keys = HashSet::with_capacity(n);
+        let mut attempts = 0;
+        while keys.len() < n {
+            assert!(attempts < max_attempts && attempts <= u32::MAX as usize,
+                    "too few distinct keys after {} attempts", attempts);
+            keys.insert(prf.call(attempts as u32).split_gen::<K>());
+            attempts += 1;
+        }
+        self.split_map(keys)
+    }
+
+    /// Generate an association list of `len` key/value pairs.  Pair
+    /// number `i` is generated off branch `i` of a PRF split off this
+    /// generator, except that when `unique_keys` is set, pairs whose
+    /// key is already in the list are dropped and the following
+    /// branches tried instead.  That mode never returns if `K` has
+    /// fewer than `len` values, and it compares each new key to all
+    /// the previous ones.
+    fn split_assoc_list<K, V>(&mut self, len: usize, unique_keys: bool) -> Vec<(K, V)>
+        where K: SplitRand + Eq,
+              V: SplitRand
+    {
+        let prf = self.splitn();
+        let mut pairs: Vec<(K, V)> = Vec::with_capacity(len);
+        let mut i = 0;
+        while pairs.len() < len {
+            let (key, value) = prf.call(i).split_gen();
+            if !unique_keys || pairs.iter().all(|(k, _)| *k != key) {
+                pairs.push((key, value));
+            }
+            i += 1;
+        }
+        pairs
+    }
+
+    /// Partition `items` into `k` groups at random.  The group of item
+    /// number `i` is picked uniformly off branch `i` of a PRF split off
+    /// this generator, so it depends on the item's position but not on
+    /// the other items.  Items keep their relative order within each
+    /// group, and some groups may be empty.
+    ///
+    /// # Panics
+    ///
+    /// If `k` is zero.
+    fn split_partition<T>(&mut self, items: Vec<T>, k: usize) -> Vec<Vec<T>> {
+        assert!(k > 0, "split_partition needs at least one group");
+        let prf = self.splitn();
+        let mut groups: Vec<Vec<T>> = (0..k).map(|_| Vec::new()).collect();
+        for (i, item) in items.into_iter().enumerate() {
+            let group = prf.call(i as u32).gen_range(0, k);
+            groups[group].push(item);
+        }
+        groups
+    }
+
+    /// Choose a subset of `items`, in their order, including each one
+    /// independently with probability `p`.  Whether item `i` is in is
+    /// drawn off branch `i` of a PRF split off this generator, so it
+    /// depends neither on the other items nor on how many there are.
+    ///
+    /// # Panics
+    ///
+    /// Unless `0 <= p <= 1`, or if there are more than `u32::MAX + 1`
+    /// items.
+    fn split_gen_subset<T: Clone>(&mut self, items: &[T], p: f64) -> Vec<T> {
+        assert!((0.0..=1.0).contains(&p), "probability must be between 0 and 1");
+        assert!(items.len() as u64 <= u32::MAX as u64 + 1, "more items than PRF branches");
+        let prf = self.splitn();
+        items.iter().enumerate()
+            .filter(|&(i, _)| prf.call(i as u32).gen::<f64>() < p)
+            .map(|(_, item)| item.clone())
+            .collect()
+    }
+
+    /// Generate a uniformly random permutation of `0..n`, by a
+    /// Fisher-Yates shuffle off a new split of this generator.  So
+    /// this generator advances by the same amount whatever `n` is.
+    fn split_permutation(&mut self, n: usize) -> Vec<usize> {
+        let mut perm: Vec<usize> = (0..n).collect();
+        self.split().shuffle(&mut perm);
+        perm
+    }
+
+    /// Choose `amount` distinct indices from `0..len` uniformly at
+    /// random, off a new split of this generator, like
+    /// `rand::seq::index::sample` in later versions of `rand`.  The
+    /// indices come out in the order they entered the sample, not
+    /// sorted.
+    ///
+    /// When `amount` is small next to `len` this uses Floyd's
+    /// algorithm, which never materializes `0..len`, but then the
+    /// order of the sample is not uniformly random; otherwise it uses
+    /// a partial Fisher-Yates shuffle, whose order is.  Shuffle the
+    /// result if the order matters.
+    ///
+    /// # Panics
+    ///
+    /// If `amount > len`.
+    fn split_sample_indices(&mut self, len: usize, amount: usize) -> Vec<usize> {
+        seq::sample_indices(&mut self.split(), len, amount)
+    }
+
+    /// Choose `k` elements of `iter`, or all of them if there are
+    /// fewer, without knowing the number of elements in advance.  This
+    /// is Vitter's Algorithm R on a new split of this generator, so
+    /// the sample depends only on this generator and the sequence of
+    /// elements, and this generator advances by one split however long
+    /// the sequence is.  Every element is equally likely to be in the
+    /// sample, but the sample's order is not uniformly random.
+    fn split_reservoir<T, I>(&mut self, iter: I, k: usize) -> Vec<T>
+        where I: Iterator<Item=T>
+    {
+        seq::reservoir(&mut self.split(), iter, k)
+    }
+
+    /// Choose `k` distinct indices into `weights`, each with
+    /// probability proportional to its weight, off a new split of this
+    /// generator.  The indices come out in the order they were picked,
+    /// as if drawn one at a time without replacement.  Indices with
+    /// zero weight are never chosen, so fewer than `k` may come back,
+    /// except that if `k` is at least `weights.len()` every index is
+    /// returned in order.
+    ///
+    /// This uses the A-ExpJ algorithm of Efraimidis and Spirakis,
+    /// which needs random numbers only for the indices that enter the
+    /// sample, not for every element of `weights`.
+    ///
+    /// # Panics
+    ///
+    /// If any weight is negative, infinite or NaN.
+    fn split_sample_weighted(&mut self, weights: &[f64], k: usize) -> Vec<usize> {
+        seq::sample_weighted(&mut self.split(), weights, k)
+    }
+
+    /// Shuffle `slice` off a new split of this generator so that
+    /// elements of higher weight tend to come first: the result is
+    /// distributed like a sequence of draws without replacement, each
+    /// with probability proportional to weight.  Elements of zero
+    /// weight end up last, in uniformly random order.
+    ///
+    /// # Panics
+    ///
+    /// If any weight is negative, infinite or NaN.
+    fn split_shuffle_weighted<T, F>(&mut self, slice: &mut [T], weight: F)
+        where F: Fn(&T) -> f64
+    {
+        seq::shuffle_weighted(&mut self.split(), slice, weight)
+    }
+
+    /// Choose an element of `slice` with probability proportional to
+    /// the weight that `weight` gives it, off a new split of this
+    /// generator.  Returns `None` if the slice is empty.  If all the
+    /// weights are zero, falls back to choosing uniformly.
+    ///
+    /// # Panics
+    ///
+    /// If any weight is negative, infinite or NaN.
+    fn split_choose_weighted<'a, T, F>(&mut self, slice: &'a [T], weight: F) -> Option<&'a T>
+        where F: Fn(&T) -> f64
+    {
+        seq::choose_weighted(&mut self.split(), slice, weight)
+    }
+
+    /// Take one step of a Markov chain: draw the state that follows
+    /// `state`, with probabilities given by row `state` of the
+    /// `transition` matrix, off a new split of this generator.  The
+    /// rows need not sum to exactly 1, since they are used as weights;
+    /// a state with zero weight is never drawn.  Calling this in a loop
+    /// gives a random walk that is determined by the generator.
+    ///
+    /// # Panics
+    ///
+    /// If `state` is not a row of `transition`, or the row has no
+    /// positive weight, or any weight is negative, infinite or NaN.
+    fn split_markov_step(&mut self, transition: &[Vec<f64>], state: usize) -> usize {
+        let row = &transition[state];
+        assert!(row.iter().any(|&w| w > 0.0), "state {} has no transitions", state);
+        let states: Vec<usize> = (0..row.len()).collect();
+        *seq::choose_weighted(&mut self.split(), &states, |&j| row[j]).unwrap()
+    }
+
+    /// Draw a random probability vector from the Dirichlet
+    /// distribution with parameters `alpha`: the result has one
+    /// component per parameter, each in `[0, 1]`, summing to 1, and
+    /// component `i` has mean `alpha[i] / alpha.iter().sum()`.  An
+    /// empty `alpha` gives an empty vector.
+    ///
+    /// Component `i` is a Gamma draw off branch `i` of a PRF split off
+    /// this generator, divided by the sum of all the draws.  So only
+    /// the unnormalized draw for component `i` depends on nothing but
+    /// its position; adding a parameter changes every component.
+    ///
+    /// # Panics
+    ///
+    /// If any parameter is zero, negative, infinite or NaN.
+    fn split_dirichlet(&mut self, alpha: &[f64]) -> Vec<f64> {
+        distributions::dirichlet(self, alpha)
+    }
+}
+
+/// Generate a random value of a `SplitRand` type.  This is the same
+/// as `rng.split_gen()`, for callers who prefer function syntax.
+pub fn split_rand<T: SplitRand, R: SplitRng>(rng: &mut R) -> T {
+    T::split_rand(rng)
 }
 
 /// Pseudo-random functions ("PRFs") generated off a `SplitRng`.
@@ -266,6 +827,37 @@ pub trait SplitRand {
     
 }
 
+/// Iterator that generates random values off successive splits of a
+/// `SplitRng`.  See `SplitRng::split_sample_iter`.
+pub struct SplitGenerator<'a, T, R: 'a> {
+    rng: &'a mut R,
+    _marker: PhantomData<fn() -> T>
+}
+
+impl<'a, T: SplitRand, R: SplitRng> Iterator for SplitGenerator<'a, T, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        Some(self.rng.split().split_gen())
+    }
+}
+
+/// Iterator that generates pairs of random values off two
+/// independent streams.  See `SplitRng::zip_gen`.
+pub struct ZipGenerator<A, B, R> {
+    left:  R,
+    right: R,
+    _marker: PhantomData<fn() -> (A, B)>
+}
+
+impl<A: SplitRand, B: SplitRand, R: SplitRng> Iterator for ZipGenerator<A, B, R> {
+    type Item = (A, B);
+
+    fn next(&mut self) -> Option<(A, B)> {
+        Some((self.left.split().split_gen(), self.right.split().split_gen()))
+    }
+}
+
 /// A newtype wrapper to add a `SplitRand` implementation to `Rand`
 /// types.  This just does the same thing as the base type's `Rand`
 /// one does.
@@ -286,22 +878,165 @@ impl<A: Hash, B: Rand> SplitRand for Box<dyn Fn(A) -> B> {
     fn split_rand<R>(rng: &mut R) -> Self 
         where R: SplitRng
     {
-        let (k0, k1) = (rng.next_u64(), rng.next_u64());
-        let prf = rng.splitn();
+        let prf = HashPrf::new(rng);
         Box::new(move |arg: A| {
-            let i: u32 = {
-                // TODO: is there a way not to hardcode `SipHasher` here?
-                #[allow(deprecated)]
-                let mut hasher = SipHasher::new_with_keys(k0, k1);
-                arg.hash(&mut hasher);
-                (hasher.finish() & 0xffff_ffff) as u32
-            };
-            Rand::rand(&mut prf.call(i))
+            Rand::rand(&mut prf.call::<A, R>(&arg))
         })
     }
 
 }
 
+/// A random function that may fail.  The `Result` for each argument
+/// is generated with its `SplitRand` implementation, off a branch
+/// selected by hashing the argument, so both the payload and the error
+/// only need to be `SplitRand`.
+pub struct SplitFallibleFn<A, B, E>(Box<dyn Fn(A) -> Result<B, E>>);
+
+impl<A, B, E> SplitFallibleFn<A, B, E> {
+    /// Apply the function to `arg`.
+    pub fn call(&self, arg: A) -> Result<B, E> {
+        (self.0)(arg)
+    }
+
+    /// Unwrap the boxed closure.
+    pub fn into_inner(self) -> Box<dyn Fn(A) -> Result<B, E>> {
+        self.0
+    }
+}
+
+impl<A, B, E> SplitRand for SplitFallibleFn<A, B, E>
+    where A: Hash,
+          B: SplitRand,
+          E: SplitRand
+{
+    fn split_rand<R>(rng: &mut R) -> Self
+        where R: SplitRng
+    {
+        let prf = HashPrf::new(rng);
+        SplitFallibleFn(Box::new(move |arg: A| {
+            prf.call::<A, R>(&arg).split_gen()
+        }))
+    }
+}
+
+/// A `BuildHasher` for `HashMap`s and `HashSet`s whose SipHash key is
+/// generated by `SplitRand`, off a new split.  The iteration order of
+/// a map depends on its hasher's key, so maps built with hashers
+/// generated off the same generator state iterate in the same order,
+/// and maps built with hashers from different branches generally
+/// don't.  That makes code that wrongly depends on iteration order
+/// fail reproducibly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitBuildHasher {
+    k0: u64,
+    k1: u64
+}
+
+impl SplitRand for SplitBuildHasher {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> SplitBuildHasher {
+        let mut keys = rng.split();
+        SplitBuildHasher { k0: keys.next_u64(), k1: keys.next_u64() }
+    }
+}
+
+#[allow(deprecated)]
+impl BuildHasher for SplitBuildHasher {
+    type Hasher = SipHasher;
+
+    fn build_hasher(&self) -> SipHasher {
+        SipHasher::new_with_keys(self.k0, self.k1)
+    }
+}
+
+/// A lookup table that holds the values of a random function over a
+/// finite domain.  Generated off the same generator state, a table
+/// agrees with the boxed closure that `SplitRand` generates whenever
+/// the value type's `SplitRand` and `Rand` implementations agree, as
+/// the primitive ones do, but its lookups are cheap and its contents
+/// can be inspected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitTable<A: Hash + Eq, B> {
+    table: HashMap<A, B>
+}
+
+impl<A: Hash + Eq, B: SplitRand> SplitTable<A, B> {
+    /// Generate the table for the given domain.  Each value is
+    /// generated off a branch selected by hashing its argument, as
+    /// with `SplitRng::split_map`.
+    pub fn generate<R, I>(rng: &mut R, domain: I) -> SplitTable<A, B>
+        where R: SplitRng,
+              I: IntoIterator<Item=A>
+    {
+        SplitTable { table: rng.split_map(domain) }
+    }
+}
+
+impl<A: Hash + Eq, B> SplitTable<A, B> {
+    /// The value for `arg`, or `None` if it is outside the domain.
+    pub fn get(&self, arg: &A) -> Option<&B> {
+        self.table.get(arg)
+    }
+
+    /// The whole table.
+    pub fn as_map(&self) -> &HashMap<A, B> {
+        &self.table
+    }
+
+    /// Unwrap the table.
+    pub fn into_map(self) -> HashMap<A, B> {
+        self.table
+    }
+}
+
+
+/// A PRF whose branches are addressed by labels.  See
+/// `SplitRng::splitn_labeled`.
+pub struct LabeledPrf<R: SplitRng>(HashPrf<R::Prf>);
+
+impl<R: SplitRng> LabeledPrf<R> {
+    /// Return the generator for `label`.  The label is hashed to a
+    /// `u32` branch index, so among about `2^16` labels two are likely
+    /// to share a generator.
+    pub fn call<K: Hash + ?Sized>(&self, label: &K) -> R {
+        self.0.call(label)
+    }
+}
+
+/// A PRF whose branches are addressed by hashing arbitrary keys
+/// instead of by `u32` indices.
+struct HashPrf<F> {
+    k0:  u64,
+    k1:  u64,
+    prf: F
+}
+
+impl<F> HashPrf<F> {
+    fn new<R>(rng: &mut R) -> HashPrf<F>
+        where R: SplitRng<Prf=F>
+    {
+        let (k0, k1) = (rng.next_u64(), rng.next_u64());
+        HashPrf {
+            k0,
+            k1,
+            prf: rng.splitn()
+        }
+    }
+
+    fn call<A, R>(&self, key: &A) -> R
+        where A: Hash + ?Sized,
+              F: SplitPrf<R>
+    {
+        let i: u32 = {
+            // TODO: is there a way not to hardcode `SipHasher` here?
+            #[allow(deprecated)]
+            let mut hasher = SipHasher::new_with_keys(self.k0, self.k1);
+            key.hash(&mut hasher);
+            (hasher.finish() & 0xffff_ffff) as u32
+        };
+        self.prf.call(i)
+    }
+}
+
 /// A macro that implements `SplitRand` sequentially for any type that
 /// has a `Rand` implementation, simply by using that.  This is meant
 /// to be used for "atomic" types whose generation doesn't benefit
@@ -383,6 +1118,50 @@ tuple_impl!{A, B, C, D, E, F, G, H, I, J, K}
 tuple_impl!{A, B, C, D, E, F, G, H, I, J, K, L}
 
 
+/// A newtype wrapper around a tuple of `Rand` types, whose `SplitRand`
+/// implementation draws all the elements in order from a single split,
+/// instead of each off its own split like the plain tuple impl does.
+///
+/// This splits once whatever the arity, so it is cheaper; but an
+/// element is only independent of the others' values, not of how
+/// much randomness they consumed, so changing the type of one element
+/// can change those after it.
+pub struct SeqTuple<T>(pub T);
+
+macro_rules! seq_tuple_impl {
+    ($($tyvar:ident),* ) => {
+        impl<
+            $( $tyvar : Rand ),*
+            > SplitRand for SeqTuple<( $( $tyvar ),* , )> {
+
+            #[inline]
+            fn split_rand<R: SplitRng>(rng: &mut R) -> Self {
+                let mut sub = rng.split();
+                SeqTuple((
+                    $(
+                        sub.gen::<$tyvar>()
+                    ),*
+                    ,
+                ))
+            }
+        }
+    }
+}
+
+seq_tuple_impl!{A}
+seq_tuple_impl!{A, B}
+seq_tuple_impl!{A, B, C}
+seq_tuple_impl!{A, B, C, D}
+seq_tuple_impl!{A, B, C, D, E}
+seq_tuple_impl!{A, B, C, D, E, F}
+seq_tuple_impl!{A, B, C, D, E, F, G}
+seq_tuple_impl!{A, B, C, D, E, F, G, H}
+seq_tuple_impl!{A, B, C, D, E, F, G, H, I}
+seq_tuple_impl!{A, B, C, D, E, F, G, H, I, J}
+seq_tuple_impl!{A, B, C, D, E, F, G, H, I, J, K}
+seq_tuple_impl!{A, B, C, D, E, F, G, H, I, J, K, L}
+
+
 // Adapted, with minor modifications, from the `rand` crate.
 macro_rules! array_impl {
     {$n:expr, $t:ident, $($ts:ident,)*} => {
@@ -418,7 +1197,10 @@ mod tests {
     //! children modules.
 
     use rand::SeedableRng;
-    use ::{SplitRng, SplitPrf, SplitRand};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::thread;
+    use ::{SplitBuildHasher, SplitFallibleFn, SplitRng, SplitPrf, SplitRand, SplitTable};
 
     /// Test that generation of tuple elements with `SplitRand` is
     /// independent.
@@ -477,6 +1259,69 @@ mod tests {
     }
 
 
+    /// Test that a tuple of closures generates each closure off its
+    /// own split.
+    pub fn test_split_rand_closure_tuple<R: SplitRng>(rng: &mut R) {
+        type F = Box<dyn Fn(u32) -> u64>;
+        type G = Box<dyn Fn(String) -> bool>;
+
+        let prf = rng.splitn();
+        for i in 0..10 {
+            let (fa, ga, ha): (F, G, F) = prf.call(i).split_gen();
+            let (fb, gb, hb): (F, G, F) = prf.call(i).split_gen();
+            for x in 0..100 {
+                assert_eq!(fa(x), fb(x));
+                assert_eq!(ga(x.to_string()), gb(x.to_string()));
+                assert_eq!(ha(x), hb(x));
+            }
+            // Closures of the same type in different positions are
+            // different functions.
+            assert!((0..100).all(|x| fa(x) != ha(x)));
+            let falses = (0..1000).filter(|x| !ga(x.to_string())).count();
+            assert!(400 < falses && falses < 600);
+        }
+    }
+
+    /// Test generation of fallible functions.
+    pub fn test_split_rand_fallible_closure<R: SplitRng>(rng: &mut R) {
+        type F = SplitFallibleFn<String, u64, (u8, bool)>;
+
+        let prf = rng.splitn();
+        let i = rng.gen();
+        let fa: F = prf.call(i).split_gen();
+        let fb: F = prf.call(i).split_gen();
+        let mut oks = 0;
+        for n in 0..1000 {
+            let arg = n.to_string();
+            let ya = fa.call(arg.clone());
+            assert_eq!(ya, fb.call(arg.clone()));
+            assert_eq!(ya, fa.call(arg));
+            if ya.is_ok() {
+                oks += 1;
+            }
+        }
+        assert!(400 < oks && oks < 600);
+    }
+
+
+    /// Test that generated closures can be shared between threads.
+    pub fn test_split_rand_closure_threads<R>(rng: &mut R)
+        where R: SplitRng,
+              R::Prf: Send + Sync
+    {
+        let f: Arc<Box<dyn Fn(u64) -> [u64; 4] + Send + Sync>> =
+            Arc::new(rng.split_gen_sync_fn());
+        let handles: Vec<_> = (0..4).map(|_| {
+            let f = f.clone();
+            thread::spawn(move || (0..100).map(|x| f(x)).collect::<Vec<_>>())
+        }).collect();
+        let expected: Vec<_> = (0..100).map(|x| f(x)).collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+    }
+
+
     /// Test that splitting a generator produces reproducible
     /// sequential results.
     pub fn test_split_rand_split<R: SplitRng>(rng: &mut R) {
@@ -498,6 +1343,496 @@ mod tests {
     }
 
 
+    /// Test that the values generated by `split_map` depend only on
+    /// their keys.
+    pub fn test_split_map<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let i = rng.gen();
+
+        let keys: Vec<String> = (0..100).map(|n| n.to_string()).collect();
+        let ma: HashMap<String, u64> =
+            prf.call(i).split_map(keys.iter().cloned());
+        let mb: HashMap<String, u64> =
+            prf.call(i).split_map(keys.iter().rev().cloned());
+        let mc: HashMap<String, u64> =
+            prf.call(i).split_map(keys.iter().step_by(3).cloned());
+
+        assert_eq!(ma.len(), keys.len());
+        assert_eq!(ma, mb);
+        for (key, value) in mc.iter() {
+            assert_eq!(ma[key], *value);
+        }
+    }
+
+
+    pub fn test_split_markov_step<R: SplitRng>(rng: &mut R) {
+        // A ring of four states that moves forward, or from state 2
+        // back to 0, and never stays put.
+        let transition = vec![
+            vec![0.0, 1.0, 0.0, 0.0],
+            vec![0.0, 0.0, 1.0, 0.0],
+            vec![0.5, 0.0, 0.0, 0.5],
+            vec![1.0, 0.0, 0.0, 0.0],
+        ];
+        let walk = |mut rng: R| {
+            let mut state = 0;
+            let mut states = Vec::with_capacity(1000);
+            for _ in 0..1000 {
+                state = rng.split_markov_step(&transition, state);
+                states.push(state);
+            }
+            states
+        };
+        let prf = rng.splitn();
+        let states = walk(prf.call(0));
+        assert_eq!(states, walk(prf.call(0)));
+        assert!(states != walk(prf.call(1)));
+        let mut previous = 0;
+        for &state in states.iter() {
+            assert!(transition[previous][state] > 0.0);
+            previous = state;
+        }
+        assert!(states.contains(&3));
+    }
+
+    pub fn test_split_map_exact<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        for i in 0..20 {
+            for &n in &[0, 1, 10, 200, 256] {
+                let map: HashMap<u8, u64> = prf.call(i).split_map_exact(n);
+                assert_eq!(map.len(), n);
+                assert_eq!(map, prf.call(i).split_map_exact(n));
+            }
+            let map: HashMap<String, u64> = prf.call(i).split_map_exact(100);
+            assert_eq!(map.len(), 100);
+        }
+    }
+
+    /// Test that the items produced by `split_sample_iter` don't
+    /// depend on each other.
+    pub fn test_split_sample_iter<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let i = rng.gen();
+        let mut ra: R = prf.call(i);
+        let mut rb: R = prf.call(i);
+
+        let _: Option<[u64; 32]> = ra.split_sample_iter().next();
+        let _: Option<u8> = rb.split_sample_iter().next();
+
+        let xs: Vec<(u32, u32)> = ra.split_sample_iter().take(100).collect();
+        let ys: Vec<(u32, u32)> = rb.split_sample_iter().take(100).collect();
+        assert_eq!(xs, ys);
+        for pair in xs.windows(2) {
+            assert!(pair[0] != pair[1]);
+        }
+    }
+
+    /// Test that `split_permutation` generates permutations that
+    /// depend only on the branch they are generated off.
+    pub fn test_split_permutation<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let i = rng.gen();
+        let (mut a0, mut a1) = prf.call(i).split2();
+        let (_, mut b1) = prf.call(i).split2();
+
+        let _: [u64; 32] = a0.gen();
+        let _ = a0.split_permutation(100);
+        let pa = a1.split_permutation(100);
+        let pb = b1.split_permutation(100);
+        assert_eq!(pa, pb);
+
+        let mut sorted = pa.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..100).collect::<Vec<_>>());
+        assert!(pa != sorted);
+
+        let _ = a1.split_permutation(5);
+        let _ = b1.split_permutation(50);
+        assert_eq!(a1.next_u64(), b1.next_u64());
+        assert!(a1.split_permutation(0).is_empty());
+    }
+
+    /// Test both modes of `split_assoc_list`.
+    pub fn test_split_assoc_list<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let i = rng.gen();
+
+        let a: Vec<(u8, u64)> = prf.call(i).split_assoc_list(100, false);
+        let b: Vec<(u8, u64)> = prf.call(i).split_assoc_list(50, false);
+        assert_eq!(a.len(), 100);
+        assert_eq!(a[..50], b[..]);
+
+        let c: Vec<(u8, u64)> = prf.call(i).split_assoc_list(200, true);
+        let d: Vec<(u8, u64)> = prf.call(i).split_assoc_list(200, true);
+        assert_eq!(c, d);
+        let mut keys: Vec<u8> = c.iter().map(|&(k, _)| k).collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), 200);
+    }
+
+    /// Test that the entry points for generating `SplitRand` values
+    /// agree.
+    pub fn test_split_gen_entry_points<R: SplitRng>(rng: &mut R) {
+        type T = (u32, [u8; 4], Vec<(u16, String)>);
+
+        let prf = rng.splitn();
+        let i = rng.gen();
+        let a: T = prf.call(i).split_gen();
+        let b: T = ::split_rand(&mut prf.call(i));
+        let c = <T as SplitRand>::split_rand(&mut prf.call(i));
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+
+        let x: u64 = prf.call(i).split_gen();
+        let y: u64 = prf.call(i).gen();
+        assert_eq!(x, y);
+    }
+
+    /// Test that `split_partition` keeps every item exactly once and
+    /// assigns groups by position.
+    pub fn test_split_partition<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let i = rng.gen();
+
+        let items: Vec<u32> = (0..1000).collect();
+        let a = prf.call(i).split_partition(items.clone(), 7);
+        let b = prf.call(i).split_partition(items.clone(), 7);
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 7);
+        assert!(a.iter().all(|group| !group.is_empty()));
+
+        let mut all: Vec<u32> = a.iter().flat_map(|g| g.iter().cloned()).collect();
+        all.sort();
+        assert_eq!(all, items);
+
+        let c = prf.call(i).split_partition(items[..10].to_vec(), 7);
+        for (group, prefix) in a.iter().zip(c.iter()) {
+            let expected: Vec<u32> = group.iter().cloned().filter(|&x| x < 10).collect();
+            assert_eq!(*prefix, expected);
+        }
+    }
+
+    /// Test that a `SplitTable` agrees with the generated closure on
+    /// its domain and nowhere else.
+    pub fn test_split_table<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let i = rng.gen();
+        let domain: Vec<String> = (0..100).map(|j| format!("key{}", j)).collect();
+
+        let table: SplitTable<String, u64> =
+            SplitTable::generate(&mut prf.call(i), domain.iter().cloned());
+        let f: Box<dyn Fn(String) -> u64> = prf.call(i).split_gen();
+        assert_eq!(table.as_map().len(), domain.len());
+        for key in domain.iter() {
+            assert_eq!(table.get(key), Some(&f(key.clone())));
+        }
+        assert_eq!(table.get(&"other".to_string()), None);
+
+        let again = SplitTable::generate(&mut prf.call(i), domain.iter().cloned());
+        assert_eq!(table, again);
+    }
+
+    pub fn test_split_build_hasher<R: SplitRng>(rng: &mut R) {
+        fn keys(hasher: SplitBuildHasher) -> Vec<u32> {
+            let mut map = HashMap::with_hasher(hasher);
+            for key in 0..100u32 {
+                map.insert(key, ());
+            }
+            map.keys().cloned().collect()
+        }
+
+        let prf = rng.splitn();
+        let order = keys(prf.call(0).split_gen());
+        assert_eq!(order, keys(prf.call(0).split_gen()));
+        let differing = (1..20).filter(|&i| keys(prf.call(i).split_gen()) != order).count();
+        assert!(differing >= 18);
+    }
+
+    pub fn test_split_fold<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let sums: Vec<u64> = (0..10).map(|i| {
+            let sum = prf.call(i).split_fold(1000, 0u64, |acc, x: u8| acc + x as u64);
+            assert_eq!(sum, prf.call(i).split_fold(1000, 0u64, |acc, x: u8| acc + x as u64));
+            let many: Vec<u8> = prf.call(i).split_many(1000);
+            assert_eq!(sum, many.iter().map(|&x| x as u64).sum::<u64>());
+            sum
+        }).collect();
+        assert!(sums.iter().all(|&sum| 100_000 < sum && sum < 155_000));
+        assert!(sums.windows(2).any(|w| w[0] != w[1]));
+        assert_eq!(rng.split_fold(0, 7, |acc, x: u8| acc + x), 7);
+    }
+
+    /// Code written against the traits alone, as a downstream crate
+    /// would write it.
+    pub fn test_generic_consumer<R: SplitRng>(rng: &mut R) {
+        #[derive(Debug, PartialEq)]
+        struct World {
+            seed:    u64,
+            terrain: Vec<u8>,
+            weather: (bool, Vec<u32>),
+        }
+
+        fn build<R: SplitRng>(rng: &mut R) -> World {
+            let subsystems = rng.splitn_labeled();
+            World {
+                seed:    rng.split_gen(),
+                terrain: subsystems.call("terrain").split_many(16),
+                weather: subsystems.call("weather").split_gen(),
+            }
+        }
+
+        let prf = rng.splitn();
+        let world = build(&mut prf.call(0));
+        assert_eq!(world, build(&mut prf.call(0)));
+        assert!(world != build(&mut prf.call(1)));
+
+        let labels = prf.call(2).splitn_labeled();
+        let a: Vec<u64> = labels.call("terrain").split_many(4);
+        let b: Vec<u64> = labels.call(&"terrain".to_string()).split_many(4);
+        let c: Vec<u64> = labels.call("weather").split_many(4);
+        assert_eq!(a, b);
+        assert!(a != c);
+    }
+
+    pub fn test_split_gen_bitset<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        for &n in &[0, 1, 63, 64, 65, 1000] {
+            let bits = prf.call(0).split_gen_bitset(n);
+            assert_eq!(bits, prf.call(0).split_gen_bitset(n));
+            assert_eq!(bits.len(), n.div_ceil(64));
+            if !n.is_multiple_of(64) {
+                assert_eq!(bits[bits.len() - 1] >> (n % 64), 0);
+            }
+            let mut split = prf.call(0).split();
+            for (i, &word) in bits.iter().enumerate() {
+                let full = split.next_u64();
+                if i + 1 < bits.len() || n.is_multiple_of(64) {
+                    assert_eq!(word, full);
+                }
+            }
+        }
+        let ones: u32 = prf.call(1).split_gen_bitset(6400).iter().map(|w| w.count_ones()).sum();
+        assert!(3000 < ones && ones < 3400);
+    }
+
+    pub fn test_split_grid<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        for i in 0..10 {
+            let grid: Vec<Vec<u64>> = prf.call(i).split_grid(4, 5);
+            assert_eq!(grid, prf.call(i).split_grid::<u64>(4, 5));
+            assert_eq!(grid.len(), 4);
+            assert!(grid.iter().all(|row| row.len() == 5));
+
+            let taller: Vec<Vec<u64>> = prf.call(i).split_grid(9, 5);
+            assert_eq!(grid[2][3], taller[2][3]);
+            assert_eq!(grid[..], taller[..4]);
+            let flat: Vec<u64> = prf.call(i).split_many(20);
+            assert_eq!(grid.concat(), flat);
+
+            let wider: Vec<Vec<u64>> = prf.call(i).split_grid(4, 6);
+            assert!(grid[2][3] != wider[2][3]);
+        }
+        assert!(rng.split_grid::<u8>(0, 3).is_empty());
+        assert!(rng.split_grid::<u8>(3, 0).iter().all(|row| row.is_empty()));
+    }
+
+    pub fn test_split_map_filter_gen<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        for i in 0..100 {
+            let doubled = prf.call(i).split_map_gen(|x: u8| 2 * x as u16);
+            assert_eq!(doubled, 2 * prf.call(i).split_gen::<u8>() as u16);
+            assert_eq!(doubled, prf.call(i).split_map_gen(|x: u8| 2 * x as u16));
+
+            let even: u32 = prf.call(i).split_filter_gen(64, |x| x % 2 == 0).unwrap();
+            assert_eq!(even % 2, 0);
+            assert_eq!(prf.call(i).split_filter_gen(64, |x: &u32| x & 1 == 0), Some(even));
+
+            let mut ra = prf.call(i);
+            let mut rb = prf.call(i);
+            let _: Option<u32> = ra.split_filter_gen(64, |x| x % 2 == 0);
+            let _: Option<u32> = rb.split_filter_gen(64, |x| x % 1000 == 0);
+            assert_eq!(ra.next_u64(), rb.next_u64());
+        }
+        assert_eq!(rng.split_filter_gen(100, |_: &u64| false), None);
+        assert_eq!(rng.split_filter_gen(0, |_: &u64| true), None);
+    }
+
+    pub fn test_split_many<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let i = rng.gen();
+
+        let many: Vec<u64> = prf.call(i).split_many(5);
+        let branches = prf.call(i).splitn();
+        let manual: Vec<u64> = (0..5).map(|j| branches.call(j).split_gen()).collect();
+        assert_eq!(many, manual);
+
+        let short: Vec<u64> = prf.call(i).split_many(3);
+        let long: Vec<u64> = prf.call(i).split_many(30);
+        assert_eq!(short[2], long[2]);
+        assert_eq!(short[..], long[..3]);
+
+        let mut ra = prf.call(i);
+        let mut rb = prf.call(i);
+        let _: Vec<u64> = ra.split_many(0);
+        let _: Vec<u64> = rb.split_many(17);
+        assert_eq!(ra.next_u64(), rb.next_u64());
+
+        let mut buffer: Vec<u64> = vec![1, 2, 3];
+        prf.call(i).fill(&mut buffer, 30);
+        assert_eq!(buffer.len(), 33);
+        assert_eq!(buffer[..3], [1, 2, 3]);
+        assert_eq!(buffer[3..], long[..]);
+    }
+
+    pub fn test_zip_gen<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let i = rng.gen();
+
+        let (mut left, mut right) = prf.call(i).zip_streams();
+        let (l, r): (Vec<u64>, Vec<u64>) = (left.gen_iter().take(100).collect(),
+                                            right.gen_iter().take(100).collect());
+        assert!(l.iter().zip(r.iter()).all(|(a, b)| a != b));
+
+        let pairs: Vec<(u64, Vec<u8>)> = prf.call(i).zip_gen().take(50).collect();
+        let again: Vec<(u64, Vec<u8>)> = prf.call(i).zip_gen().take(50).collect();
+        assert_eq!(pairs, again);
+
+        // The first elements don't depend on the type of the second.
+        let other: Vec<(u64, bool)> = prf.call(i).zip_gen().take(50).collect();
+        assert!(pairs.iter().zip(other.iter()).all(|(p, o)| p.0 == o.0));
+
+        let (mut left, _) = prf.call(i).zip_streams();
+        assert!(pairs.iter().all(|p| p.0 == left.split().split_gen::<u64>()));
+    }
+
+    pub fn test_split_gen_subset<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let items: Vec<u32> = (0..200).collect();
+        for i in 0..20 {
+            let subset = prf.call(i).split_gen_subset(&items, 0.3);
+            assert_eq!(subset, prf.call(i).split_gen_subset(&items, 0.3));
+            assert!(subset.windows(2).all(|w| w[0] < w[1]));
+            assert!((30..90).contains(&subset.len()));
+
+            // Membership of the first items doesn't depend on the rest.
+            let prefix = prf.call(i).split_gen_subset(&items[..50], 0.3);
+            let expected: Vec<u32> = subset.iter().cloned().filter(|&x| x < 50).collect();
+            assert_eq!(prefix, expected);
+
+            assert_eq!(prf.call(i).split_gen_subset(&items, 1.0), items);
+            assert!(prf.call(i).split_gen_subset(&items, 0.0).is_empty());
+        }
+    }
+
+    pub fn test_split_gen_timestamps<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        for i in 0..20 {
+            let ts = prf.call(i).split_gen_timestamps(1000, 100, 5);
+            assert_eq!(ts, prf.call(i).split_gen_timestamps(1000, 100, 5));
+            assert_eq!(ts.len(), 100);
+            assert_eq!(ts[0], 1000);
+            assert!(ts.windows(2).all(|w| w[0] < w[1] && w[1] - w[0] <= 5));
+            assert_eq!(prf.call(i).split_gen_timestamps(1000, 10, 5), &ts[..10]);
+            assert!(ts != prf.call(i + 20).split_gen_timestamps(1000, 100, 5));
+        }
+        let ones = rng.split_gen_timestamps(7, 4, 1);
+        assert_eq!(ones, vec![7, 8, 9, 10]);
+        assert!(rng.split_gen_timestamps(0, 0, 3).is_empty());
+    }
+
+    pub fn test_split_gen_uuid<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        for i in 0..100 {
+            let uuid = prf.call(i).split_gen_uuid();
+            assert_eq!(uuid, prf.call(i).split_gen_uuid());
+            assert_eq!(uuid[6] >> 4, 4);
+            assert_eq!(uuid[8] >> 6, 0b10);
+            assert!(uuid != prf.call(i + 100).split_gen_uuid());
+        }
+    }
+
+    pub fn test_split_gen_normal<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let i = rng.gen();
+        assert_eq!(prf.call(i).split_gen_normal(3.0, 2.0),
+                   prf.call(i).split_gen_normal(3.0, 2.0));
+
+        let mut rng = prf.call(i);
+        let n = 100_000;
+        let (mut sum, mut sum_sq) = (0.0, 0.0);
+        for _ in 0..n {
+            let x = rng.split_gen_normal(3.0, 2.0);
+            sum += x;
+            sum_sq += x * x;
+        }
+        let mean = sum / n as f64;
+        let variance = sum_sq / n as f64 - mean * mean;
+        assert!((mean - 3.0).abs() < 0.05);
+        assert!((variance - 4.0).abs() < 0.1);
+    }
+
+    /// Test that `split_gen_bools` unpacks words in the documented
+    /// order.
+    pub fn test_split_gen_bools<R: SplitRng>(rng: &mut R) {
+        let prf = rng.splitn();
+        let i = rng.gen();
+        let a: [bool; 130] = prf.call(i).split_gen_bools();
+        let b: [bool; 130] = prf.call(i).split_gen_bools();
+        assert_eq!(a[..], b[..]);
+
+        let mut words = prf.call(i);
+        for chunk in a.chunks(64) {
+            let word = words.next_u64();
+            for (j, &bit) in chunk.iter().enumerate() {
+                assert_eq!(bit, word & (1 << j) != 0);
+            }
+        }
+
+        let mut rb = prf.call(i);
+        let _: [bool; 0] = rb.split_gen_bools();
+        let _: [bool; 64] = rb.split_gen_bools();
+        let _: [bool; 1] = rb.split_gen_bools();
+        let mut rc = prf.call(i);
+        let _ = (rc.next_u64(), rc.next_u64());
+        assert_eq!(rb.next_u64(), rc.next_u64());
+    }
+
+    /// Test that a recursive computation that runs the halves
+    /// produced by `split2` in parallel gets the same result as a
+    /// serial one.
+    pub fn test_split2<R: SplitRng + Send>(rng: &mut R) {
+        fn serial_sum<R: SplitRng>(rng: &mut R, depth: u32) -> u64 {
+            if depth == 0 {
+                return rng.gen::<u32>() as u64;
+            }
+            let (mut left, mut right) = rng.split2();
+            serial_sum(&mut left, depth - 1) + serial_sum(&mut right, depth - 1)
+        }
+
+        fn parallel_sum<R: SplitRng + Send>(rng: &mut R, depth: u32) -> u64 {
+            if depth == 0 {
+                return rng.gen::<u32>() as u64;
+            }
+            let (mut left, mut right) = rng.split2();
+            let handle = thread::spawn(move || {
+                parallel_sum(&mut left, depth - 1)
+            });
+            let b = parallel_sum(&mut right, depth - 1);
+            handle.join().unwrap() + b
+        }
+
+        let prf = rng.splitn();
+        let i = rng.gen();
+        let (mut left, mut right) = prf.call(i).split2();
+        assert!(left.next_u64() != right.next_u64());
+
+        let a = serial_sum(&mut prf.call(i), 5);
+        let b = parallel_sum(&mut prf.call(i), 5);
+        assert_eq!(a, b);
+    }
+
+
     /*
      * The tests below here are lightly adapted from the `rand` crate.
      */
@@ -533,7 +1868,7 @@ mod tests {
     }
 
 
-    fn iter_eq<I, J>(i: I, j: J) -> bool
+    pub fn iter_eq<I, J>(i: I, j: J) -> bool
         where I: IntoIterator,
               J: IntoIterator<Item=I::Item>,
               I::Item: Eq
@@ -0,0 +1,298 @@
+//! The `split_rand!` macro.
+
+
+/// Generate a struct, tuple struct, tuple or enum whose fields are
+/// each generated off an explicitly numbered branch.
+///
+/// ```
+/// #[macro_use]
+/// extern crate rand_split;
+///
+/// use rand_split::siprng::SipRng;
+///
+/// struct Config {
+///     retries: u32,
+///     name:    Vec<u8>,
+/// }
+///
+/// struct Point(u32, u32);
+///
+/// # fn main() {
+/// let mut rng = SipRng::new(1, 2);
+/// let config = split_rand!(&mut rng => Config {
+///     retries: u32 @ 0,
+///     name:    Vec<u8> @ 1,
+/// });
+/// let point = split_rand!(&mut rng => Point(u32 @ 0, u32 @ 1));
+/// let pair: (u8, bool) = split_rand!(&mut rng => (u8 @ 7, bool @ 3));
+/// # }
+/// ```
+///
+/// The macro splits a single PRF off the generator, and generates the
+/// field tagged `@ i` off `prf.call(i)`.  This is a stability
+/// contract: the value of a field depends only on the state of the
+/// generator and on the field's type and index.  So fields can be
+/// added at fresh indices, removed or reordered without changing the
+/// values of the others, and the generator itself always advances by
+/// exactly one split.  Fields given the same index of the same type
+/// get the same value.
+///
+/// An enum is generated by picking one of the listed variants
+/// uniformly, and generating its fields like those of a struct.  Each
+/// variant is tagged with a branch too, which its fields are
+/// generated off, and the variants are ranked by branch rather than
+/// by where they are listed:
+///
+/// ```
+/// #[macro_use]
+/// extern crate rand_split;
+///
+/// use rand_split::siprng::SipRng;
+///
+/// enum Shape {
+///     Circle(u32),
+///     Rect { w: u32, h: u32 },
+///     Empty,
+/// }
+///
+/// # fn main() {
+/// let shape = split_rand!(&mut SipRng::new(1, 2) => enum Shape {
+///     Circle(u32 @ 0) @ 0,
+///     Rect { w: u32 @ 0, h: u32 @ 1 } @ 1,
+///     Empty @ 2,
+/// });
+/// # }
+/// ```
+///
+/// The variant is picked off one branch of a PRF split off the
+/// generator, and the fields of the variant tagged `@ n` are generated
+/// off branch `n` of another.  So listing the variants in another
+/// order changes nothing, and adding a variant at a fresh branch
+/// changes how often each variant is picked, but whenever a variant
+/// is picked its fields are the same as before.
+#[macro_export]
+macro_rules! split_rand {
+    ($rng:expr => enum $name:ident { $($variants:tt)* }) => {{
+        let top = $crate::SplitRng::splitn($rng);
+        let mut chooser = $crate::SplitPrf::call(&top, 0);
+        let payloads = $crate::SplitRng::splitn(&mut $crate::SplitPrf::call(&top, 1));
+        split_rand!(@enum chooser, payloads, $name, [], $($variants)*)
+    }};
+    ($rng:expr => $name:ident { $($fields:tt)* }) => {{
+        let prf = $crate::SplitRng::splitn($rng);
+        split_rand!(@named prf, [$name], [], $($fields)*)
+    }};
+    ($rng:expr => $name:ident ( $($fields:tt)* )) => {{
+        let prf = $crate::SplitRng::splitn($rng);
+        split_rand!(@tuple prf, [$name], [], [], $($fields)*)
+    }};
+    ($rng:expr => ( $($fields:tt)* )) => {{
+        let prf = $crate::SplitRng::splitn($rng);
+        split_rand!(@tuple prf, [], [], [], $($fields)*)
+    }};
+
+    // Generate one field off a branch.
+    (@field $prf:ident, [$($ty:tt)*], $i:expr) => {{
+        let mut rng = $crate::SplitPrf::call(&$prf, $i);
+        let value: $($ty)* = $crate::SplitRng::split_gen(&mut rng);
+        value
+    }};
+
+    // Named fields: munch the tokens of each field's type until `@`.
+    (@named $prf:ident, [$($name:tt)*], [$($done:tt)*], ) => {
+        $($name)* { $($done)* }
+    };
+    (@named $prf:ident, [$($name:tt)*], [$($done:tt)*], $field:ident : $($rest:tt)*) => {
+        split_rand!(@named_ty $prf, [$($name)*], [$($done)*], $field, [], $($rest)*)
+    };
+    (@named_ty $prf:ident, [$($name:tt)*], [$($done:tt)*], $field:ident, [$($ty:tt)*],
+     @ $i:expr) => {
+        split_rand!(@named $prf, [$($name)*],
+                    [$($done)* $field: split_rand!(@field $prf, [$($ty)*], $i),], )
+    };
+    (@named_ty $prf:ident, [$($name:tt)*], [$($done:tt)*], $field:ident, [$($ty:tt)*],
+     @ $i:expr, $($rest:tt)*) => {
+        split_rand!(@named $prf, [$($name)*],
+                    [$($done)* $field: split_rand!(@field $prf, [$($ty)*], $i),],
+                    $($rest)*)
+    };
+    (@named_ty $prf:ident, [$($name:tt)*], [$($done:tt)*], $field:ident, [$($ty:tt)*],
+     $next:tt $($rest:tt)*) => {
+        split_rand!(@named_ty $prf, [$($name)*], [$($done)*], $field, [$($ty)* $next],
+                    $($rest)*)
+    };
+
+    // Positional fields, likewise.
+    (@tuple $prf:ident, [$($name:tt)*], [$($done:tt)*], [], ) => {
+        $($name)* ( $($done)* )
+    };
+    (@tuple $prf:ident, [$($name:tt)*], [$($done:tt)*], [$($ty:tt)*], @ $i:expr) => {
+        split_rand!(@tuple $prf, [$($name)*],
+                    [$($done)* split_rand!(@field $prf, [$($ty)*], $i),], [], )
+    };
+    (@tuple $prf:ident, [$($name:tt)*], [$($done:tt)*], [$($ty:tt)*],
+     @ $i:expr, $($rest:tt)*) => {
+        split_rand!(@tuple $prf, [$($name)*],
+                    [$($done)* split_rand!(@field $prf, [$($ty)*], $i),], [],
+                    $($rest)*)
+    };
+    (@tuple $prf:ident, [$($name:tt)*], [$($done:tt)*], [$($ty:tt)*],
+     $next:tt $($rest:tt)*) => {
+        split_rand!(@tuple $prf, [$($name)*], [$($done)*], [$($ty)* $next], $($rest)*)
+    };
+
+    // Enum variants: collect a constructor for each, then pick one.
+    (@enum $chooser:ident, $payloads:ident, $name:ident, [$($done:tt)*], ) => {{
+        let mut variants: Vec<(u32, Box<dyn Fn() -> $name>)> = vec![$($done),*];
+        variants.sort_by_key(|variant| variant.0);
+        let k = $crate::prelude::Rng::gen_range(&mut $chooser, 0, variants.len());
+        (variants[k].1)()
+    }};
+    (@enum $chooser:ident, $payloads:ident, $name:ident, [$($done:tt)*],
+     $variant:ident ( $($fields:tt)* ) @ $n:expr $(, $($rest:tt)*)?) => {
+        split_rand!(@enum $chooser, $payloads, $name, [$($done)* ($n, Box::new(|| {
+            let prf = $crate::SplitRng::splitn(&mut $crate::SplitPrf::call(&$payloads, $n));
+            split_rand!(@tuple prf, [$name :: $variant], [], [], $($fields)*)
+        }))], $($($rest)*)?)
+    };
+    (@enum $chooser:ident, $payloads:ident, $name:ident, [$($done:tt)*],
+     $variant:ident { $($fields:tt)* } @ $n:expr $(, $($rest:tt)*)?) => {
+        split_rand!(@enum $chooser, $payloads, $name, [$($done)* ($n, Box::new(|| {
+            let prf = $crate::SplitRng::splitn(&mut $crate::SplitPrf::call(&$payloads, $n));
+            split_rand!(@named prf, [$name :: $variant], [], $($fields)*)
+        }))], $($($rest)*)?)
+    };
+    (@enum $chooser:ident, $payloads:ident, $name:ident, [$($done:tt)*],
+     $variant:ident @ $n:expr $(, $($rest:tt)*)?) => {
+        split_rand!(@enum $chooser, $payloads, $name, [$($done)* ($n, Box::new(|| {
+            $name :: $variant
+        }))], $($($rest)*)?)
+    };
+}
+
+
+#[cfg(test)]
+mod tests {
+    use siprng::SipRng;
+
+
+    #[derive(Debug, PartialEq)]
+    struct V1 {
+        id:   u64,
+        tags: Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct V2 {
+        id:    u64,
+        score: [u16; 4],
+        tags:  Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Pair(u32, (u8, bool));
+
+    #[derive(Debug, PartialEq)]
+    enum E1 {
+        A(u32),
+        B { x: u64, y: bool },
+        C,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum E2 {
+        A(u32),
+        D(u8, u8),
+        B { x: u64, y: bool },
+        C,
+    }
+
+    #[test]
+    fn test_split_rand_fields_stable() {
+        for seed in 0..100 {
+            let v1 = split_rand!(&mut SipRng::from_u64(seed) => V1 {
+                id:   u64 @ 0,
+                tags: Vec<u8> @ 1
+            });
+            let v2 = split_rand!(&mut SipRng::from_u64(seed) => V2 {
+                tags:  Vec<u8> @ 1,
+                score: [u16; 4] @ 2,
+                id:    u64 @ 0,
+            });
+            assert_eq!(v1.id, v2.id);
+            assert_eq!(v1.tags, v2.tags);
+        }
+    }
+
+    #[test]
+    fn test_split_rand_enum_stable() {
+        // Inserting a variant at a fresh branch changes which variant
+        // is picked, but not the fields of a variant once picked.
+        let mut seen = [false; 4];
+        let mut common = 0;
+        for seed in 0..200 {
+            let e1 = split_rand!(&mut SipRng::from_u64(seed) => enum E1 {
+                A(u32 @ 0) @ 0,
+                B { x: u64 @ 0, y: bool @ 1 } @ 1,
+                C @ 2,
+            });
+            let e2 = split_rand!(&mut SipRng::from_u64(seed) => enum E2 {
+                A(u32 @ 0) @ 0,
+                D(u8 @ 0, u8 @ 1) @ 3,
+                B { x: u64 @ 0, y: bool @ 1 } @ 1,
+                C @ 2
+            });
+            match (&e1, &e2) {
+                (&E1::A(a1), &E2::A(a2)) => { assert_eq!(a1, a2); common += 1; }
+                (&E1::B { x: x1, y: y1 }, &E2::B { x: x2, y: y2 }) => {
+                    assert_eq!((x1, y1), (x2, y2));
+                    common += 1;
+                }
+                _ => {}
+            }
+            seen[match e2 { E2::A(_) => 0, E2::D(..) => 1, E2::B { .. } => 2, E2::C => 3 }] = true;
+            assert_eq!(e1, split_rand!(&mut SipRng::from_u64(seed) => enum E1 {
+                A(u32 @ 0) @ 0,
+                B { x: u64 @ 0, y: bool @ 1 } @ 1,
+                C @ 2,
+            }));
+        }
+        assert!(seen.iter().all(|&s| s));
+        assert!(common > 0);
+    }
+
+    #[test]
+    fn test_split_rand_enum_order() {
+        // Variants are ranked by branch, so listing them in another
+        // order picks the same one.
+        for seed in 0..200 {
+            let e1 = split_rand!(&mut SipRng::from_u64(seed) => enum E2 {
+                A(u32 @ 0) @ 0,
+                D(u8 @ 0, u8 @ 1) @ 3,
+                B { x: u64 @ 0, y: bool @ 1 } @ 1,
+                C @ 2
+            });
+            let e2 = split_rand!(&mut SipRng::from_u64(seed) => enum E2 {
+                C @ 2,
+                B { x: u64 @ 0, y: bool @ 1 } @ 1,
+                A(u32 @ 0) @ 0,
+                D(u8 @ 0, u8 @ 1) @ 3,
+            });
+            assert_eq!(e1, e2);
+        }
+    }
+
+    #[test]
+    fn test_split_rand_tuples() {
+        let mut ra = SipRng::from_u64(7);
+        let mut rb = SipRng::from_u64(7);
+        let a = split_rand!(&mut ra => Pair(u32 @ 0, (u8, bool) @ 1));
+        let (b0, b1): (u32, (u8, bool)) = split_rand!(&mut rb => (u32 @ 0, (u8, bool) @ 1,));
+        assert_eq!(a, Pair(b0, b1));
+
+        let c: (u32, u32) = split_rand!(&mut ra => (u32 @ 5, u32 @ 5));
+        assert_eq!(c.0, c.1);
+        let d: (u32,) = split_rand!(&mut rb => (u32 @ 5));
+        assert_eq!(d.0, c.0);
+    }
+}
@@ -0,0 +1,250 @@
+//! Generating strings that match a small pattern language.
+//!
+//! A pattern is a sequence of atoms, each optionally followed by a
+//! quantifier:
+//!
+//! * a literal character, or any character escaped with `\`;
+//! * `.`, which stands for any printable ASCII character;
+//! * a class such as `[a-z_0-9]`, of characters and inclusive ranges;
+//!   a `-` at either end is literal, and there is no negation;
+//! * `?` repeats the atom before it zero or one times, `*` zero to
+//!   `MAX_REPEAT` times and `+` one to `MAX_REPEAT` times.
+//!
+//! There is no grouping or alternation.
+
+use rand::Rng;
+use std::error::Error;
+use std::fmt;
+use super::{SplitRng, SplitPrf};
+
+
+/// The most times that `*` and `+` repeat an atom.
+pub const MAX_REPEAT: u32 = 8;
+
+/// A parsed pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    items: Vec<Item>
+}
+
+/// The errors that `Pattern::parse` reports, with the byte offset in
+/// the pattern where they were found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternError {
+    /// A `[` without a matching `]`.
+    UnclosedClass(usize),
+    /// A class with no characters, or a range whose ends are in the
+    /// wrong order or that spans the UTF-16 surrogates.
+    BadClass(usize),
+    /// A quantifier with no atom before it.
+    NothingToRepeat(usize),
+    /// A `\` at the end of the pattern.
+    TrailingEscape(usize),
+}
+
+/// An atom and how many times to repeat it, inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item {
+    ranges: Vec<(char, char)>,
+    min:    u32,
+    max:    u32
+}
+
+impl Pattern {
+    /// Parse `pattern`.
+    pub fn parse(pattern: &str) -> Result<Pattern, PatternError> {
+        let mut items: Vec<Item> = Vec::new();
+        let mut chars = pattern.char_indices();
+        while let Some((pos, c)) = chars.next() {
+            let ranges = match c {
+                '?' | '*' | '+' => {
+                    let (min, max) = match c {
+                        '?' => (0, 1),
+                        '*' => (0, MAX_REPEAT),
+                        _   => (1, MAX_REPEAT),
+                    };
+                    match items.last_mut() {
+                        Some(item) if item.min == 1 && item.max == 1 => {
+                            item.min = min;
+                            item.max = max;
+                        }
+                        _ => return Err(PatternError::NothingToRepeat(pos)),
+                    }
+                    continue;
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => vec![(escaped, escaped)],
+                    None => return Err(PatternError::TrailingEscape(pos)),
+                },
+                '.' => vec![(' ', '~')],
+                '[' => {
+                    let mut members = Vec::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, ']')) => break,
+                            Some((_, member)) => members.push(member),
+                            None => return Err(PatternError::UnclosedClass(pos)),
+                        }
+                    }
+                    parse_class(&members).ok_or(PatternError::BadClass(pos))?
+                }
+                _ => vec![(c, c)],
+            };
+            items.push(Item { ranges, min: 1, max: 1 });
+        }
+        Ok(Pattern { items })
+    }
+
+    /// Generate a string that matches the pattern.  Atom number `i` is
+    /// generated off branch `i` of a PRF split off `rng`: its number
+    /// of repetitions off one split, and its `j`th character off
+    /// branch `j` of a PRF split off another.  So each character
+    /// depends on neither the characters nor the repetitions before
+    /// it.
+    pub fn generate<R: SplitRng>(&self, rng: &mut R) -> String {
+        let prf = rng.splitn();
+        let mut result = String::new();
+        for (i, item) in self.items.iter().enumerate() {
+            let mut item_rng = prf.call(i as u32);
+            let count = item_rng.split().gen_range(item.min, item.max + 1);
+            let chars = item_rng.splitn();
+            for j in 0..count {
+                result.push(item.choose(&mut chars.call(j)));
+            }
+        }
+        result
+    }
+
+    /// Whether `s` matches the pattern, as a whole.
+    pub fn matches(&self, s: &str) -> bool {
+        let chars: Vec<char> = s.chars().collect();
+        matches_from(&self.items, &chars)
+    }
+}
+
+impl Item {
+    /// Draw a character uniformly from the atom's ranges.
+    fn choose<R: Rng>(&self, rng: &mut R) -> char {
+        let size = |&(lo, hi): &(char, char)| hi as u32 - lo as u32 + 1;
+        let total: u32 = self.ranges.iter().map(size).sum();
+        let mut n = rng.gen_range(0, total);
+        for range in self.ranges.iter() {
+            if n < size(range) {
+                return ::std::char::from_u32(range.0 as u32 + n)
+                    .expect("class range spans the surrogates");
+            }
+            n -= size(range);
+        }
+        unreachable!()
+    }
+
+    fn contains(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi)
+    }
+}
+
+/// Parse the members of a class, between the brackets.
+fn parse_class(members: &[char]) -> Option<Vec<(char, char)>> {
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < members.len() {
+        if i + 2 < members.len() && members[i + 1] == '-' {
+            let (lo, hi) = (members[i], members[i + 2]);
+            if lo > hi || ((lo as u32) < 0xd800 && (hi as u32) > 0xdfff) {
+                return None;
+            }
+            ranges.push((lo, hi));
+            i += 3;
+        } else {
+            ranges.push((members[i], members[i]));
+            i += 1;
+        }
+    }
+    if ranges.is_empty() { None } else { Some(ranges) }
+}
+
+/// Backtracking matcher.
+fn matches_from(items: &[Item], s: &[char]) -> bool {
+    let item = match items.first() {
+        Some(item) => item,
+        None => return s.is_empty(),
+    };
+    let longest = s.iter().take(item.max as usize).take_while(|&&c| item.contains(c)).count();
+    (item.min as usize..=longest).rev().any(|n| matches_from(&items[1..], &s[n..]))
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PatternError::UnclosedClass(pos) =>
+                write!(f, "unclosed character class at {}", pos),
+            PatternError::BadClass(pos) =>
+                write!(f, "empty or reversed character class at {}", pos),
+            PatternError::NothingToRepeat(pos) =>
+                write!(f, "quantifier with nothing to repeat at {}", pos),
+            PatternError::TrailingEscape(pos) =>
+                write!(f, "trailing escape at {}", pos),
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+
+#[cfg(test)]
+mod tests {
+    use siprng::SipRng;
+    use ::SplitRng;
+    use super::{Pattern, PatternError, MAX_REPEAT};
+
+
+    #[test]
+    fn test_generate_matches() {
+        let patterns = [
+            "abc",
+            "[a-z]+@[a-z]+\\.com",
+            "id-[0-9]?[0-9]*x",
+            "[-_a-cX]*.?",
+            "\\[\\*\\]+",
+            "",
+        ];
+        for pattern in patterns.iter() {
+            let parsed = Pattern::parse(pattern).unwrap();
+            for seed in 0..200 {
+                let s = parsed.generate(&mut SipRng::from_u64(seed));
+                assert_eq!(s, parsed.generate(&mut SipRng::from_u64(seed)));
+                assert!(parsed.matches(&s), "{:?} doesn't match {:?}", s, pattern);
+            }
+        }
+        assert!(!Pattern::parse("a+b").unwrap().matches("b"));
+        assert!(!Pattern::parse("a?").unwrap().matches("aa"));
+        assert!(Pattern::parse("a*a").unwrap().matches("aaa"));
+    }
+
+    #[test]
+    fn test_generate_positional() {
+        // The characters of an atom don't depend on how many times
+        // it's repeated, nor on the atoms before it.
+        let short = Pattern::parse("0?[a-z]+").unwrap();
+        let long = Pattern::parse("1*[a-z]+").unwrap();
+        for seed in 0..100 {
+            let a = short.generate(&mut SipRng::from_u64(seed));
+            let b = long.generate(&mut SipRng::from_u64(seed));
+            assert_eq!(a.trim_start_matches('0'), b.trim_start_matches('1'));
+        }
+        let s = Pattern::parse("a*").unwrap().generate(&mut SipRng::from_u64(0));
+        assert!(s.len() <= MAX_REPEAT as usize);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Pattern::parse("ab[cd"), Err(PatternError::UnclosedClass(2)));
+        assert_eq!(Pattern::parse("a[]"), Err(PatternError::BadClass(1)));
+        assert_eq!(Pattern::parse("[z-a]"), Err(PatternError::BadClass(0)));
+        assert_eq!(Pattern::parse("*a"), Err(PatternError::NothingToRepeat(0)));
+        assert_eq!(Pattern::parse("a+?"), Err(PatternError::NothingToRepeat(2)));
+        assert_eq!(Pattern::parse("a\\"), Err(PatternError::TrailingEscape(1)));
+        assert!(SipRng::from_u64(0).split_gen_string_matching("[").is_err());
+    }
+
+}
@@ -0,0 +1,14 @@
+//! The traits and types needed for typical use, to import in one go:
+//!
+//! ```
+//! use rand_split::prelude::*;
+//!
+//! let mut rng = SipRng::new(1, 2);
+//! let (a, b): (u32, Vec<bool>) = rng.split_gen();
+//! let prf = rng.splitn();
+//! let x: u64 = prf.call(7).gen();
+//! ```
+
+pub use rand::{Rng, SeedableRng};
+pub use siprng::SipRng;
+pub use super::{split_rand, Seq, SeqTuple, SplitPrf, SplitRand, SplitRng};
@@ -0,0 +1,300 @@
+//! Sampling of numbers from ranges.
+
+use rand::{Closed01, Rng};
+use std::ops::{Range, RangeInclusive};
+
+
+/// Integer types that can be drawn uniformly from an inclusive range,
+/// including ranges that end at the type's maximum.
+pub trait SampleInclusive : Sized {
+    /// Draw a value uniformly from `[low, high]`.  Panics if `low >
+    /// high`.
+    fn sample_inclusive<R: Rng>(rng: &mut R, low: Self, high: Self) -> Self;
+}
+
+/// Implements `SampleInclusive` in terms of `Rng::gen_range`, whose
+/// half-open ranges are sampled without bias.  The 128-bit types, which
+/// `gen_range` doesn't support, are given with the unsigned type of the
+/// same width and drawn by rejection instead.
+macro_rules! sample_inclusive_impl {
+    ($t:ident, $u:ident) => {
+        impl SampleInclusive for $t {
+            fn sample_inclusive<R: Rng>(rng: &mut R, low: $t, high: $t) -> $t {
+                assert!(low <= high, "sample_inclusive called with low > high");
+                let width = (high as $u).wrapping_sub(low as $u).wrapping_add(1);
+                if width == 0 {
+                    gen_u128(rng) as $t
+                } else {
+                    (low as $u).wrapping_add(gen_u128_below(rng, width)) as $t
+                }
+            }
+        }
+    };
+    ($t:ident) => {
+        impl SampleInclusive for $t {
+            #[inline]
+            fn sample_inclusive<R: Rng>(rng: &mut R, low: $t, high: $t) -> $t {
+                assert!(low <= high, "sample_inclusive called with low > high");
+                if high < $t::MAX {
+                    rng.gen_range(low, high + 1)
+                } else if low > $t::MIN {
+                    rng.gen_range(low - 1, high) + 1
+                } else {
+                    rng.gen()
+                }
+            }
+        }
+    };
+}
+
+sample_inclusive_impl!{isize}
+sample_inclusive_impl!{i8}
+sample_inclusive_impl!{i16}
+sample_inclusive_impl!{i32}
+sample_inclusive_impl!{i64}
+
+sample_inclusive_impl!{usize}
+sample_inclusive_impl!{u8}
+sample_inclusive_impl!{u16}
+sample_inclusive_impl!{u32}
+sample_inclusive_impl!{u64}
+
+sample_inclusive_impl!{i128, u128}
+sample_inclusive_impl!{u128, u128}
+
+
+/// Ranges that a value of type `T` can be drawn from uniformly.  See
+/// `SplitRng::split_gen_within`.
+pub trait SplitSampleRange<T> {
+    /// Draw a value uniformly from the range.  Panics if the range is
+    /// empty.
+    fn sample_split<R: Rng>(self, rng: &mut R) -> T;
+}
+
+/// Implements `SplitSampleRange` for the integer ranges, on top of
+/// `Rng::gen_range` and `SampleInclusive`.
+macro_rules! split_sample_range_int_impl {
+    ($t:ident) => {
+        impl SplitSampleRange<$t> for Range<$t> {
+            #[inline]
+            fn sample_split<R: Rng>(self, rng: &mut R) -> $t {
+                assert!(self.start < self.end, "cannot sample from an empty range");
+                rng.gen_range(self.start, self.end)
+            }
+        }
+
+        impl SplitSampleRange<$t> for RangeInclusive<$t> {
+            #[inline]
+            fn sample_split<R: Rng>(self, rng: &mut R) -> $t {
+                let (low, high) = self.into_inner();
+                $t::sample_inclusive(rng, low, high)
+            }
+        }
+    }
+}
+
+/// Implements `SplitSampleRange` for the floating-point ranges, by
+/// scaling a number drawn uniformly from `[0, 1)` or `[0, 1]`.
+macro_rules! split_sample_range_float_impl {
+    ($t:ident) => {
+        impl SplitSampleRange<$t> for Range<$t> {
+            fn sample_split<R: Rng>(self, rng: &mut R) -> $t {
+                assert!(self.start < self.end, "cannot sample from an empty range");
+                let width = self.end - self.start;
+                assert!(width.is_finite(), "cannot sample from an unbounded range");
+                loop {
+                    // Rounding can land on the end of the range.
+                    let x = self.start + width * rng.gen::<$t>();
+                    if x < self.end {
+                        return x;
+                    }
+                }
+            }
+        }
+
+        impl SplitSampleRange<$t> for RangeInclusive<$t> {
+            fn sample_split<R: Rng>(self, rng: &mut R) -> $t {
+                let (low, high) = self.into_inner();
+                assert!(low <= high, "cannot sample from an empty range");
+                let width = high - low;
+                assert!(width.is_finite(), "cannot sample from an unbounded range");
+                let Closed01(u) = rng.gen::<Closed01<$t>>();
+                (low + width * u).min(high)
+            }
+        }
+    }
+}
+
+/// Implements `SplitSampleRange` for the 128-bit integer ranges, which
+/// `Rng::gen_range` doesn't support, with `$u` the unsigned type of the
+/// same width.
+macro_rules! split_sample_range_wide_impl {
+    ($t:ident, $u:ident) => {
+        impl SplitSampleRange<$t> for Range<$t> {
+            fn sample_split<R: Rng>(self, rng: &mut R) -> $t {
+                assert!(self.start < self.end, "cannot sample from an empty range");
+                let width = (self.end as $u).wrapping_sub(self.start as $u);
+                (self.start as $u).wrapping_add(gen_u128_below(rng, width)) as $t
+            }
+        }
+
+        impl SplitSampleRange<$t> for RangeInclusive<$t> {
+            #[inline]
+            fn sample_split<R: Rng>(self, rng: &mut R) -> $t {
+                let (low, high) = self.into_inner();
+                $t::sample_inclusive(rng, low, high)
+            }
+        }
+    }
+}
+
+split_sample_range_int_impl!{isize}
+split_sample_range_int_impl!{i8}
+split_sample_range_int_impl!{i16}
+split_sample_range_int_impl!{i32}
+split_sample_range_int_impl!{i64}
+
+split_sample_range_int_impl!{usize}
+split_sample_range_int_impl!{u8}
+split_sample_range_int_impl!{u16}
+split_sample_range_int_impl!{u32}
+split_sample_range_int_impl!{u64}
+
+split_sample_range_wide_impl!{i128, u128}
+split_sample_range_wide_impl!{u128, u128}
+
+split_sample_range_float_impl!{f32}
+split_sample_range_float_impl!{f64}
+
+
+/// Draw a `u128` from two `next_u64` words, the high one first.
+fn gen_u128<R: Rng>(rng: &mut R) -> u128 {
+    let hi = rng.next_u64() as u128;
+    hi << 64 | rng.next_u64() as u128
+}
+
+/// Draw a `u128` uniformly from `[0, width)`, rejecting the draws below
+/// `2^128 mod width` so that each remainder is equally likely.
+fn gen_u128_below<R: Rng>(rng: &mut R, width: u128) -> u128 {
+    debug_assert!(width > 0);
+    let threshold = width.wrapping_neg() % width;
+    loop {
+        let x = gen_u128(rng);
+        if x >= threshold {
+            return x % width;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use siprng::SipRng;
+    use std::num::Saturating;
+    use ::SplitRng;
+    use super::SampleInclusive;
+
+
+    #[test]
+    fn test_sample_inclusive_endpoints() {
+        let mut rng = SipRng::from_u64(1);
+        let mut seen = [false; 5];
+        for _ in 0..1000 {
+            let Saturating(x) = rng.split_gen_saturating(3u8, 7);
+            assert!((3..=7).contains(&x));
+            seen[(x - 3) as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+
+        let mut seen = [false; 3];
+        for _ in 0..1000 {
+            let x = i8::sample_inclusive(&mut rng, i8::MAX - 2, i8::MAX);
+            seen[(x - (i8::MAX - 2)) as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    #[test]
+    fn test_sample_inclusive_degenerate() {
+        let mut rng = SipRng::from_u64(2);
+        assert_eq!(u64::sample_inclusive(&mut rng, 9, 9), 9);
+        assert_eq!(u8::sample_inclusive(&mut rng, 255, 255), 255);
+        let _ = i32::sample_inclusive(&mut rng, i32::MIN, i32::MAX);
+    }
+
+    #[test]
+    fn test_split_gen_within() {
+        for seed in 0..100 {
+            let mut rng = SipRng::from_u64(seed);
+            let a: u32 = rng.split_gen_within(0..10);
+            let b: i64 = rng.split_gen_within(0..=10);
+            let c: f64 = rng.split_gen_within(0.0..1.0);
+            let d: f32 = rng.split_gen_within(-2.0..=2.0);
+            assert!(a < 10);
+            assert!((0..=10).contains(&b));
+            assert!((0.0..1.0).contains(&c));
+            assert!((-2.0..=2.0).contains(&d));
+
+            let mut again = SipRng::from_u64(seed);
+            assert_eq!(again.split_gen_within(0u32..10), a);
+            assert_eq!(again.split_gen_within(0i64..=10), b);
+            assert_eq!(again.split_gen_within(0.0f64..1.0), c);
+            assert_eq!(again.split_gen_within(-2.0f32..=2.0), d);
+        }
+
+        let mut rng = SipRng::from_u64(4);
+        let mut seen = [false; 11];
+        for _ in 0..1000 {
+            seen[rng.split_gen_within(0usize..=10)] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+        assert_eq!(rng.split_gen_within(u8::MAX..=u8::MAX), u8::MAX);
+        assert_eq!(rng.split_gen_within(1.5f64..=1.5), 1.5);
+    }
+
+    #[test]
+    fn test_split_gen_within_wide() {
+        let mut rng = SipRng::from_u64(6);
+        let (mut negative, mut positive) = (false, false);
+        for _ in 0..1000 {
+            let a: i128 = rng.split_gen_within(-100..=100);
+            let b: i128 = rng.split_gen_within(i128::MIN..-5);
+            let c: u128 = rng.split_gen_within(u128::MAX - 3..=u128::MAX);
+            let d: u128 = rng.split_gen_within(1 << 100..(1 << 100) + 7);
+            let e: i64 = rng.split_gen_within(-100..=100);
+            assert!((-100..=100).contains(&a));
+            assert!(b < -5);
+            assert!(c >= u128::MAX - 3);
+            assert!((1 << 100..(1 << 100) + 7).contains(&d));
+            assert!((-100..=100).contains(&e));
+            negative |= a < 0 && e < 0;
+            positive |= a > 0 && e > 0;
+        }
+        assert!(negative && positive);
+
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            seen[(rng.split_gen_within(-3i128..=3) + 3) as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+        let _ = u128::sample_inclusive(&mut rng, 0, u128::MAX);
+        let _ = i128::sample_inclusive(&mut rng, i128::MIN, i128::MAX);
+        assert_eq!(rng.split_gen_within(i128::MAX..=i128::MAX), i128::MAX);
+
+        let x: u128 = SipRng::from_u64(7).split_gen_within(0..u128::MAX);
+        assert_eq!(SipRng::from_u64(7).split_gen_within(0..u128::MAX), x);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_gen_within_empty() {
+        SipRng::from_u64(5).split_gen_within(3i16..3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sample_inclusive_empty() {
+        let mut rng = SipRng::from_u64(3);
+        u32::sample_inclusive(&mut rng, 2, 1);
+    }
+}
@@ -0,0 +1,144 @@
+//! Generating values of a type chosen at runtime.
+
+use rand::Rng;
+use siprng::SipRng;
+use super::{SplitRng, SplitPrf};
+
+
+/// A list of constructors of boxed `T`s, usually trait objects, one of
+/// which is picked at random to generate each value.
+///
+/// ```
+/// use rand_split::SplitRng;
+/// use rand_split::registry::SplitRegistry;
+/// use rand_split::siprng::SipRng;
+/// use std::fmt::Debug;
+///
+/// let mut registry: SplitRegistry<dyn Debug> = SplitRegistry::new();
+/// registry
+///     .register(|rng| Box::new(rng.split_gen::<u32>()))
+///     .register(|rng| Box::new(rng.split_gen::<(bool, char)>()));
+/// let value = registry.generate(&mut SipRng::new(1, 2)).unwrap();
+/// println!("{:?}", value);
+/// ```
+pub struct SplitRegistry<T: ?Sized> {
+    constructors: Vec<Constructor<T>>
+}
+
+type Constructor<T> = Box<dyn Fn(&mut SipRng) -> Box<T>>;
+
+impl<T: ?Sized> SplitRegistry<T> {
+    /// Create an empty registry.
+    pub fn new() -> SplitRegistry<T> {
+        SplitRegistry { constructors: Vec::new() }
+    }
+
+    /// Add a constructor.
+    pub fn register<F>(&mut self, constructor: F) -> &mut SplitRegistry<T>
+        where F: Fn(&mut SipRng) -> Box<T> + 'static
+    {
+        self.constructors.push(Box::new(constructor));
+        self
+    }
+
+    /// The number of constructors registered.
+    pub fn len(&self) -> usize {
+        self.constructors.len()
+    }
+
+    /// Whether no constructor is registered.
+    pub fn is_empty(&self) -> bool {
+        self.constructors.is_empty()
+    }
+
+    /// Pick a constructor uniformly and generate a value with it, or
+    /// return `None` if the registry is empty.  The constructor is
+    /// picked off branch 0 of a PRF split off `rng`, and called with
+    /// branch 1, so the value depends only on `rng` and on the
+    /// registry.
+    pub fn generate(&self, rng: &mut SipRng) -> Option<Box<T>> {
+        self.generate_indexed(rng).map(|(_, value)| value)
+    }
+
+    /// Same as `generate`, but also return the index of the
+    /// constructor that was picked, in order of registration.
+    pub fn generate_indexed(&self, rng: &mut SipRng) -> Option<(usize, Box<T>)> {
+        if self.constructors.is_empty() {
+            return None;
+        }
+        let prf = rng.splitn();
+        let index = prf.call(0).gen_range(0, self.constructors.len());
+        let value = (self.constructors[index])(&mut prf.call(1));
+        Some((index, value))
+    }
+}
+
+impl<T: ?Sized> Default for SplitRegistry<T> {
+    fn default() -> SplitRegistry<T> {
+        SplitRegistry::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use siprng::SipRng;
+    use ::SplitRng;
+    use super::SplitRegistry;
+
+
+    trait Shape {
+        fn area(&self) -> f64;
+    }
+
+    struct Square(u8);
+
+    struct Rectangle(u8, u8);
+
+    impl Shape for Square {
+        fn area(&self) -> f64 {
+            self.0 as f64 * self.0 as f64
+        }
+    }
+
+    impl Shape for Rectangle {
+        fn area(&self) -> f64 {
+            self.0 as f64 * self.1 as f64
+        }
+    }
+
+    fn shapes() -> SplitRegistry<dyn Shape> {
+        let mut registry: SplitRegistry<dyn Shape> = SplitRegistry::new();
+        registry
+            .register(|rng| Box::new(Square(rng.split_gen())))
+            .register(|rng| {
+                let (w, h) = rng.split_gen();
+                Box::new(Rectangle(w, h))
+            });
+        registry
+    }
+
+    #[test]
+    fn test_generate() {
+        let registry = shapes();
+        assert_eq!(registry.len(), 2);
+
+        let mut counts = [0; 2];
+        for seed in 0..2000 {
+            let (index, shape) =
+                registry.generate_indexed(&mut SipRng::from_u64(seed)).unwrap();
+            let again = registry.generate(&mut SipRng::from_u64(seed)).unwrap();
+            assert_eq!(shape.area(), again.area());
+            counts[index] += 1;
+        }
+        assert!(counts.iter().all(|&count| (900..1100).contains(&count)));
+    }
+
+    #[test]
+    fn test_generate_empty() {
+        let registry: SplitRegistry<dyn Shape> = SplitRegistry::default();
+        assert!(registry.is_empty());
+        assert!(registry.generate(&mut SipRng::from_u64(0)).is_none());
+    }
+
+}
@@ -0,0 +1,368 @@
+//! Algorithms for sampling from sequences, used by the `SplitRng`
+//! methods that do so.
+
+use rand::{Open01, Rng};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+
+/// A candidate in a weighted reservoir.  The key is the logarithm of
+/// the key of Efraimidis and Spirakis' algorithm, and the ordering is
+/// reversed so that a `BinaryHeap` keeps the smallest key on top.
+struct Entry {
+    key:   f64,
+    index: usize
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Entry) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Entry) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Entry) -> Ordering {
+        other.key.partial_cmp(&self.key).expect("NaN reservoir key")
+    }
+}
+
+
+/// Choose `k` distinct indices of `weights` with the A-ExpJ algorithm
+/// of Efraimidis and Spirakis.  See `SplitRng::split_sample_weighted`.
+pub fn sample_weighted<R: Rng>(rng: &mut R, weights: &[f64], k: usize) -> Vec<usize> {
+    assert!(weights.iter().all(|w| w.is_finite() && *w >= 0.0),
+            "weights must be finite and non-negative");
+    if k >= weights.len() {
+        return (0..weights.len()).collect();
+    }
+    if k == 0 {
+        return Vec::new();
+    }
+
+    let mut heap = BinaryHeap::with_capacity(k);
+    let mut items = weights.iter().cloned().enumerate().filter(|&(_, w)| w > 0.0);
+    for (index, w) in items.by_ref().take(k) {
+        heap.push(Entry { key: log_open01(rng) / w, index });
+    }
+
+    if heap.len() == k {
+        // How much weight to skip over before the next replacement.
+        let mut skip = log_open01(rng) / threshold(&heap);
+        for (index, w) in items {
+            skip -= w;
+            if skip <= 0.0 {
+                let t = (w * threshold(&heap)).exp();
+                let Open01(u) = rng.gen::<Open01<f64>>();
+                let key = (t + (1.0 - t) * u).ln() / w;
+                heap.pop();
+                heap.push(Entry { key, index });
+                skip = log_open01(rng) / threshold(&heap);
+            }
+        }
+    }
+
+    heap.into_sorted_vec().into_iter().map(|entry| entry.index).collect()
+}
+
+/// Choose an element of `slice` with probability proportional to its
+/// weight.  See `SplitRng::split_choose_weighted`.
+pub fn choose_weighted<'a, R, T, F>(rng: &mut R, slice: &'a [T], weight: F) -> Option<&'a T>
+    where R: Rng,
+          F: Fn(&T) -> f64
+{
+    let weights: Vec<f64> = slice.iter().map(weight).collect();
+    assert!(weights.iter().all(|w| w.is_finite() && *w >= 0.0),
+            "weights must be finite and non-negative");
+    let total: f64 = weights.iter().sum();
+    if total == 0.0 {
+        return rng.choose(slice);
+    }
+
+    let target = rng.gen::<f64>() * total;
+    let mut sum = 0.0;
+    let mut last = None;
+    for (x, &w) in slice.iter().zip(weights.iter()) {
+        if w > 0.0 {
+            sum += w;
+            if target < sum {
+                return Some(x);
+            }
+            last = Some(x);
+        }
+    }
+    // Rounding can leave `target` just short of the computed total.
+    last
+}
+
+/// Order `slice` at random, favoring elements of higher weight.  See
+/// `SplitRng::split_shuffle_weighted`.
+pub fn shuffle_weighted<R, T, F>(rng: &mut R, slice: &mut [T], weight: F)
+    where R: Rng,
+          F: Fn(&T) -> f64
+{
+    // Sort on Efraimidis and Spirakis' keys, in decreasing order; the
+    // elements of zero weight come last, in uniformly random order.
+    let mut keys: Vec<(bool, f64, usize)> = slice.iter().enumerate().map(|(i, x)| {
+        let w = weight(x);
+        assert!(w.is_finite() && w >= 0.0, "weights must be finite and non-negative");
+        if w > 0.0 {
+            (true, log_open01(rng) / w, i)
+        } else {
+            (false, log_open01(rng), i)
+        }
+    }).collect();
+    keys.sort_by(|a, b| {
+        (b.0, b.1).partial_cmp(&(a.0, a.1)).expect("NaN shuffle key")
+    });
+
+    // Move each element to its position, following the cycles of the
+    // permutation.
+    let mut dest = vec![0; slice.len()];
+    for (pos, &(_, _, i)) in keys.iter().enumerate() {
+        dest[i] = pos;
+    }
+    for i in 0..slice.len() {
+        while dest[i] != i {
+            let j = dest[i];
+            slice.swap(i, j);
+            dest.swap(i, j);
+        }
+    }
+}
+
+/// Choose `amount` distinct indices from `0..len`.  See
+/// `SplitRng::split_sample_indices`.
+pub fn sample_indices<R: Rng>(rng: &mut R, len: usize, amount: usize) -> Vec<usize> {
+    assert!(amount <= len, "cannot sample {} indices from {}", amount, len);
+    if amount.saturating_mul(4) <= len {
+        // Floyd's algorithm, which needs memory only for the sample.
+        let mut seen = HashSet::with_capacity(amount);
+        let mut indices = Vec::with_capacity(amount);
+        for j in len - amount..len {
+            let t = rng.gen_range(0, j + 1);
+            let index = if seen.contains(&t) { j } else { t };
+            seen.insert(index);
+            indices.push(index);
+        }
+        indices
+    } else {
+        // A Fisher-Yates shuffle, stopped after `amount` steps.
+        let mut indices: Vec<usize> = (0..len).collect();
+        for i in 0..amount {
+            let j = rng.gen_range(i, len);
+            indices.swap(i, j);
+        }
+        indices.truncate(amount);
+        indices
+    }
+}
+
+/// Sample `k` elements of `iter` by Vitter's Algorithm R.  See
+/// `SplitRng::split_reservoir`.
+pub fn reservoir<R, T, I>(rng: &mut R, iter: I, k: usize) -> Vec<T>
+    where R: Rng,
+          I: Iterator<Item=T>
+{
+    let mut sample = Vec::with_capacity(k.min(iter.size_hint().0));
+    for (i, item) in iter.enumerate() {
+        if i < k {
+            sample.push(item);
+        } else {
+            let j = rng.gen_range(0, i + 1);
+            if j < k {
+                sample[j] = item;
+            }
+        }
+    }
+    sample
+}
+
+/// The smallest key in the reservoir.
+#[inline]
+fn threshold(heap: &BinaryHeap<Entry>) -> f64 {
+    heap.peek().expect("empty reservoir").key
+}
+
+/// The logarithm of a number drawn uniformly from the open interval
+/// (0, 1).
+#[inline]
+fn log_open01<R: Rng>(rng: &mut R) -> f64 {
+    let Open01(u) = rng.gen::<Open01<f64>>();
+    u.ln()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use siprng::SipRng;
+    use ::SplitRng;
+    use super::{reservoir, sample_indices, sample_weighted};
+
+
+    #[test]
+    fn test_sample_weighted_distinct() {
+        let weights: Vec<f64> = (0..100).map(|i| (i % 7) as f64).collect();
+        for seed in 0..100 {
+            let mut rng = SipRng::from_u64(seed);
+            let mut sample = sample_weighted(&mut rng, &weights, 20);
+            assert_eq!(sample.len(), 20);
+            assert!(sample.iter().all(|&i| weights[i] > 0.0));
+            sample.sort();
+            sample.dedup();
+            assert_eq!(sample.len(), 20);
+        }
+    }
+
+    #[test]
+    fn test_sample_weighted_all() {
+        let weights = [1.0, 0.0, 2.5, 3.0];
+        let mut rng = SipRng::from_u64(0);
+        for k in 4..8 {
+            assert_eq!(sample_weighted(&mut rng, &weights, k), vec![0, 1, 2, 3]);
+        }
+        assert!(sample_weighted(&mut rng, &weights, 0).is_empty());
+    }
+
+    #[test]
+    fn test_sample_weighted_reproducible() {
+        let weights: Vec<f64> = (1..1000).map(|i| 1.0 / i as f64).collect();
+        let a = sample_weighted(&mut SipRng::new(1, 2), &weights, 50);
+        let b = sample_weighted(&mut SipRng::new(1, 2), &weights, 50);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_sample_weighted_proportions() {
+        // Index 0 carries half the weight, so it should be the first
+        // pick about half of the time.
+        let weights = [4.0, 1.0, 1.0, 1.0, 1.0];
+        let mut rng = SipRng::from_u64(42);
+        let first = (0..10_000)
+            .filter(|_| sample_weighted(&mut rng, &weights, 2)[0] == 0)
+            .count();
+        assert!(4_500 < first && first < 5_500);
+    }
+
+    #[test]
+    fn test_choose_weighted_frequencies() {
+        let items = [(b'a', 1.0), (b'b', 2.0), (b'c', 0.0), (b'd', 5.0)];
+        let mut rng = SipRng::from_u64(7);
+        let mut counts = [0; 4];
+        for _ in 0..80_000 {
+            let &(item, _) = rng.split_choose_weighted(&items, |x| x.1).unwrap();
+            counts[(item - b'a') as usize] += 1;
+        }
+        assert_eq!(counts[2], 0);
+        for (&count, &(_, w)) in counts.iter().zip(items.iter()) {
+            let expected = 10_000.0 * w;
+            assert!((count as f64 - expected).abs() < 1_000.0);
+        }
+    }
+
+    #[test]
+    fn test_choose_weighted_degenerate() {
+        let mut rng = SipRng::from_u64(8);
+        let empty: [u32; 0] = [];
+        assert_eq!(rng.split_choose_weighted(&empty, |_| 1.0), None);
+
+        let mut counts = [0; 3];
+        for _ in 0..3_000 {
+            let &i = rng.split_choose_weighted(&[0, 1, 2], |_| 0.0).unwrap();
+            counts[i] += 1;
+        }
+        assert!(counts.iter().all(|&c| 800 < c && c < 1_200));
+
+        let mut ra = SipRng::from_u64(9);
+        let mut rb = SipRng::from_u64(9);
+        for _ in 0..100 {
+            assert_eq!(ra.split_choose_weighted(&[1, 2, 3, 4], |&x| x as f64),
+                       rb.split_choose_weighted(&[1, 2, 3, 4], |&x| x as f64));
+        }
+    }
+
+    #[test]
+    fn test_shuffle_weighted() {
+        let mut rng = SipRng::from_u64(10);
+        let mut sums = [0; 10];
+        for _ in 0..2_000 {
+            let mut items: Vec<usize> = (0..10).collect();
+            rng.split_shuffle_weighted(&mut items, |&x| x as f64);
+            let mut sorted = items.clone();
+            sorted.sort();
+            assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+            assert_eq!(items[9], 0);
+            for (pos, &x) in items.iter().enumerate() {
+                sums[x] += pos;
+            }
+        }
+        for pair in sums[1..].windows(2) {
+            assert!(pair[0] > pair[1]);
+        }
+
+        let mut a: Vec<u32> = (0..50).collect();
+        let mut b = a.clone();
+        SipRng::from_u64(11).split_shuffle_weighted(&mut a, |&x| (x % 4) as f64);
+        SipRng::from_u64(11).split_shuffle_weighted(&mut b, |&x| (x % 4) as f64);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_sample_indices() {
+        for &(len, amount) in &[(1000, 10), (1000, 600), (50, 50), (7, 0), (0, 0)] {
+            for seed in 0..20 {
+                let sample = sample_indices(&mut SipRng::from_u64(seed), len, amount);
+                assert_eq!(sample, sample_indices(&mut SipRng::from_u64(seed), len, amount));
+                assert_eq!(sample.len(), amount);
+                assert!(sample.iter().all(|&i| i < len));
+                let mut sorted = sample.clone();
+                sorted.sort();
+                sorted.dedup();
+                assert_eq!(sorted.len(), amount);
+                if amount == len {
+                    assert_eq!(sorted, (0..len).collect::<Vec<_>>());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_reservoir() {
+        let mut counts = [0; 20];
+        for seed in 0..2000 {
+            let sample = reservoir(&mut SipRng::from_u64(seed), 0..20, 5);
+            assert_eq!(sample, reservoir(&mut SipRng::from_u64(seed), 0..20, 5));
+            assert_eq!(sample.len(), 5);
+            for &x in sample.iter() {
+                counts[x] += 1;
+            }
+            assert_eq!(reservoir(&mut SipRng::from_u64(seed), 0..3, 5), vec![0, 1, 2]);
+        }
+        // Each element is in the sample a quarter of the time.
+        assert!(counts.iter().all(|&c| 400 < c && c < 600));
+        assert!(reservoir(&mut SipRng::from_u64(0), 0..20, 0).is_empty());
+        assert!(SipRng::from_u64(0).split_reservoir(0..0, 3).is_empty());
+    }
+
+    #[test]
+    fn test_reservoir_huge_k() {
+        let mut rng = SipRng::from_u64(0);
+        assert_eq!(reservoir(&mut rng, 0..3, usize::MAX), vec![0, 1, 2]);
+        assert_eq!(rng.split_reservoir((0..3).filter(|_| true), usize::MAX),
+                   vec![0, 1, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sample_indices_too_many() {
+        sample_indices(&mut SipRng::from_u64(0), 3, 4);
+    }
+
+}
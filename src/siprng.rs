@@ -35,24 +35,97 @@
 //!   Haskell*, pp. 47-58.
 
 use rand::{Rand, Rng, SeedableRng};
+use rand::os::OsRng;
 use super::{SplitRng, SplitPrf};
-use std::mem;
+use health::{self, HealthError};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+use std::rc::Rc;
 use std::u32;
 
 
 /// A splittable pseudorandom generator based on SipHash.
+///
+/// Each 64-bit output is split in two by `next_u32`, which returns
+/// the low half first and the high half on the following call.
 pub struct SipRng {
     v0:  u64,
     v1:  u64,
     v2:  u64,
     v3:  u64,
     ctr: u32,
-    len: u8
+    len: u64,
+
+    // The high half of the last output split by `next_u32`, if it
+    // hasn't been returned yet.
+    half: Option<u32>,
+
+    // The bytes of the last output split by `read` that haven't been
+    // read yet, shifted down to the low end, and how many there are.
+    tail: Option<(u64, u8)>
 }
 
 /// A PRF taken off a `SipRng`.
+///
+/// All `2^32` branches of a PRF can be used.  `call(i)` absorbs a
+/// block holding `i` into the PRF's state with the two compression
+/// rounds of SipHash-2-4, which permute the full 256-bit state, so
+/// different branches start from different states but for a chance
+/// of about `2^-192` per pair.  The fan-out of a split tree is limited
+/// only by the branch numbers, not by collisions between siblings.
 pub struct SipPrf(SipRng);
 
+/// An iterator over the branches of a PRF, in order.  See
+/// `SipRng::sequential_splits`.
+pub struct SequentialSplits {
+    prf:  SipPrf,
+    next: Option<u32>
+}
+
+/// An iterator over the nodes of a split tree, in breadth-first
+/// order.  See `SipPrf::breadth_first`.
+pub struct BreadthFirst {
+    branching: u32,
+    parent:    SipPrf,
+    next:      u32,
+    queue:     VecDeque<SipRng>
+}
+
+/// A handle to a `SipRng` that is local to the current thread.  See
+/// `split_thread_rng`.
+#[derive(Clone)]
+pub struct SplitThreadRng {
+    rng: Rc<RefCell<SipRng>>
+}
+
+/// The whole state of a `SipRng`, captured by `SipRng::checkpoint`
+/// for `SipRng::restore`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    v:    [u64; 4],
+    ctr:  u32,
+    len:  u64,
+    half: Option<u32>,
+    tail: Option<(u64, u8)>
+}
+
+/// The error returned by `SipRng::descend_checked` when a generator's
+/// depth has already saturated at `MAX_DEPTH`.  This only reflects the
+/// reporting limit of `SipRng::depth`: generators past it are still
+/// sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthError;
+
+/// The errors that can arise while seeding a `SipRng`.
+#[derive(Debug)]
+pub enum SipRngError {
+    /// The source of entropy failed.
+    Entropy(io::Error),
+}
+
 
 /// A round of the SipHash function.
 macro_rules! sip_round {
@@ -65,7 +138,21 @@ macro_rules! sip_round {
         $v2 = $v2.wrapping_add($v1); $v0 = $v0.wrapping_add($v3);
         $v1 = $v1.rotate_left(17);   $v3 = $v3.rotate_left(21);
         $v1 ^= $v2;                  $v3 ^= $v0;
-        $v2 = $v0.rotate_left(32);
+        $v2 = $v2.rotate_left(32);
+    }
+}
+
+/// Define a method that is public with the `unstable` feature, and
+/// private otherwise.
+macro_rules! unstable_fn {
+    ($(#[$attr:meta])* fn $($rest:tt)*) => {
+        #[cfg(feature = "unstable")]
+        $(#[$attr])*
+        pub fn $($rest)*
+
+        #[cfg(not(feature = "unstable"))]
+        $(#[$attr])*
+        fn $($rest)*
     }
 }
 
@@ -80,12 +167,13 @@ macro_rules! sip_block {
 }
 
 /// Compute the result of SipHash.  `$len` is the amount of data
-/// hashed, in bytes.
+/// hashed, in bytes; as in standard SipHash, only its low byte is
+/// absorbed, so callers that need the whole length to count absorb it
+/// as a block of its own first.
 macro_rules! sip_finish {
     ($v0: expr, $v1: expr, $v2: expr, $v3: expr, $len: expr) => {
         {
-            sip_block!($v0, $v1, $v2, $v3, 
-                       ($len as u64).wrapping_shl(59));
+            sip_block!($v0, $v1, $v2, $v3, ($len as u64).wrapping_shl(56));
             
             $v2 ^= 0xff;
             sip_round!($v0, $v1, $v2, $v3);
@@ -98,6 +186,27 @@ macro_rules! sip_finish {
 }
 
 
+/// The point at which `depth` saturates.  Every split, PRF call and
+/// counter overflow descends once, in the parent as well as the
+/// child.  This is not a safety bound: a generator absorbs its whole
+/// length as a block before finalizing, and that word doesn't wrap in
+/// practice, so generators at different depths always finalize
+/// differently, even beyond this.  Debug builds still assert that no
+/// generator descends past it.
+pub const MAX_DEPTH: u32 = u32::MAX;
+
+/// The block that marks a tag absorbed by `with_context`.
+const CONTEXT_BLOCK: u64 = 0xffff_fffe_0000_0000;
+
+/// The block that marks a label absorbed by `fork_labeled`.
+const LABEL_BLOCK: u64 = 0xffff_fffc_0000_0000;
+
+/// The block that separates the two states hashed by `combine`.
+const COMBINE_BLOCK: u64 = 0xffff_fffd_0000_0000;
+
+/// The block that marks a domain hashed by `with_key_and_domain`.
+const DOMAIN_BLOCK: u64 = 0xffff_fffb_0000_0000;
+
 const C0: u64 = 0x736f6d6570736575;
 const C1: u64 = 0x646f72616e646f6d;
 const C2: u64 = 0x6c7967656e657261;
@@ -112,10 +221,208 @@ impl SipRng {
             v2:  k0 ^ C2,
             v3:  k1 ^ C3,
             ctr: 0,
-            len: 0
+            len: 0,
+            half: None,
+            tail: None
+        }
+    }
+
+    /// Return the two words that this generator was keyed with, if it
+    /// is a root generator, that is, one that hasn't been split or
+    /// called from a PRF since it was keyed.  Drawing outputs doesn't
+    /// change the key, so this works whatever the root's position.
+    pub fn root_key(&self) -> Option<(u64, u64)> {
+        let (k0, k1) = (self.v0 ^ C0, self.v1 ^ C1);
+        if self.len == 0 && self.v2 == k0 ^ C2 && self.v3 == k1 ^ C3 {
+            Some((k0, k1))
+        } else {
+            None
+        }
+    }
+
+    /// Create a root generator for the logical domain `domain` under
+    /// the master key `(k0, k1)`.  The key is rehashed with the domain,
+    /// so generators for different domains are independent, and each
+    /// is a root at depth zero, with `root_key` returning the derived
+    /// key.  This differs from `SipRng::new(k0, k1).fork_labeled(domain)`,
+    /// which is a child of the master generator.
+    pub fn with_key_and_domain(k0: u64, k1: u64, domain: &str) -> SipRng {
+        let bytes = domain.as_bytes();
+        let mut blocks = vec![DOMAIN_BLOCK, bytes.len() as u64];
+        blocks.extend(label_words(bytes));
+        let (d0, d1) = SipRng::new(k0, k1).hash_state(&blocks);
+        SipRng::new(d0, d1)
+    }
+
+    /// Create a `SipRng` generator from a single `u64` seed.  This is
+    /// a convenience for callers that only have one word at hand: the
+    /// two key words are derived from `seed` with SplitMix64, so
+    /// nearby seeds still give unrelated streams, but only 2^64 of
+    /// the 2^128 keys that `new` accepts can be reached this way.
+    pub fn from_u64(seed: u64) -> SipRng {
+        let (k0, k1) = expand_seed(seed);
+        SipRng::new(k0, k1)
+    }
+
+    /// Create a `SipRng` generator keyed with two words drawn from
+    /// another generator.  This is what the `Rand` instance does.
+    pub fn from_rng<R: Rng>(rng: &mut R) -> SipRng {
+        let (k0, k1) = rng.gen::<(u64, u64)>();
+        SipRng::new(k0, k1)
+    }
+
+    /// Create a `SipRng` generator keyed from the operating system's
+    /// source of entropy.  Unlike `OsRng::new().unwrap().gen()`, this
+    /// reports failure to the caller instead of panicking.
+    pub fn try_from_entropy() -> Result<SipRng, SipRngError> {
+        let mut osrng = OsRng::new()?;
+        Ok(SipRng::from_rng(&mut osrng))
+    }
+
+    /// Create a `SipRng` generator keyed with 16 bytes read from
+    /// `source`, taken as two little-endian words.
+    pub fn try_from_reader(source: &mut dyn Read) -> Result<SipRng, SipRngError> {
+        let mut bytes = [0u8; 16];
+        source.read_exact(&mut bytes)?;
+        let mut k0 = [0u8; 8];
+        let mut k1 = [0u8; 8];
+        k0.copy_from_slice(&bytes[..8]);
+        k1.copy_from_slice(&bytes[8..]);
+        Ok(SipRng::new(u64::from_le_bytes(k0), u64::from_le_bytes(k1)))
+    }
+
+    /// Fill `dest` with random data.  This is the same as
+    /// `fill_bytes`, which for this generator never fails; it exists
+    /// for the benefit of code that is written against fallible
+    /// sources of randomness.
+    pub fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), SipRngError> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+
+    /// Position the generator within its current sub-stream, so that
+    /// the next call to `next_u64` returns output number `ctr`
+    /// (counting from zero) of that sub-stream.  A sub-stream starts
+    /// whenever the generator is constructed, split or called from a
+    /// PRF.  This leaves the key and the depth alone, so it takes
+    /// constant time.
+    pub fn advance_to(&mut self, ctr: u32) {
+        self.ctr = ctr;
+        self.half = None;
+        self.tail = None;
+    }
+
+    /// Discard the next `n` outputs, leaving the generator exactly as
+    /// `n` calls to `next_u64` would.  So the half output buffered by
+    /// `next_u32` is still returned by the next `next_u32` call, and the
+    /// bytes buffered by `read` by the next read, unless the counter
+    /// overflows on the way, which descends and drops them.
+    /// Bits buffered by a `BitReader` are the reader's, and aren't
+    /// affected.  This takes time proportional to `n / 2^32`.
+    pub fn advance_many(&mut self, mut n: u64) {
+        loop {
+            let room = (u32::MAX - self.ctr) as u64;
+            if n <= room {
+                self.ctr += n as u32;
+                return;
+            }
+            // Overflow the counter as `advance` does.
+            n -= room + 1;
+            self.ctr = u32::MAX;
+            self.descend(0);
+        }
+    }
+
+    /// Rebuild the generator at an exact position: keyed with `(k0,
+    /// k1)` as by `new`, descended through each `(ctr, i)` of `path` in
+    /// turn, where `ctr` is the position in the sub-stream at which
+    /// the descent into branch `i` happened, and then positioned at
+    /// `counter` as by `advance_to`.  `split` at position `ctr`
+    /// descends the parent by `(ctr, 0)` and the child by `(ctr, 1)`,
+    /// and `splitn` adds one of these to every generator its PRF
+    /// returns; `call(i)` descends by `(0, i)`, and a counter overflow
+    /// by `(u32::MAX, 0)`.  Positions reached through `with_context`,
+    /// `fork_bytes` or `combine` can't be expressed as a path.
+    pub fn reseed_with_position(&mut self, k0: u64, k1: u64, path: &[(u32, u32)],
+                                counter: u32) {
+        self.reseed((k0, k1));
+        for &(ctr, i) in path {
+            self.ctr = ctr;
+            self.descend(i);
+        }
+        self.advance_to(counter);
+    }
+
+    /// Return a PRF that captures the current state of this generator,
+    /// without advancing it.  Unlike `splitn`, which descends into a
+    /// fresh branch of the parent and moves the parent to another, this
+    /// leaves the parent's future output unchanged.  The flip side is
+    /// that the PRF's branches are the parent's own: `prf().call(0)` is
+    /// the state the parent will be in after its next `split`, and
+    /// `prf().call(1)` is the generator that split will return, so
+    /// don't use those branches alongside the parent.
+    pub fn prf(&self) -> SipPrf {
+        SipPrf(self.clone())
+    }
+
+    /// The number of 64-bit outputs drawn from the current sub-stream,
+    /// that is, since the generator was constructed, split, called
+    /// from a PRF or positioned with `advance_to`.  Since `next_u32`
+    /// splits outputs in two, a pair of calls to it counts once.
+    pub fn outputs_consumed(&self) -> u64 {
+        self.ctr as u64
+    }
+
+    /// Return an iterator over independent generators, one per branch
+    /// of a PRF split off this generator: `call(0)`, `call(1)`, and so
+    /// on, up to `call(u32::MAX)`.  Splitting the PRF advances this
+    /// generator, so the generators yielded by another call to
+    /// `sequential_splits` are different ones.  That's the difference
+    /// from mapping `call` over a range of an existing PRF, which
+    /// yields the same generators every time.  Unlike
+    /// `split_sample_iter`, the iterator doesn't borrow this generator.
+    pub fn sequential_splits(&mut self) -> SequentialSplits {
+        SequentialSplits {
+            prf:  self.splitn(),
+            next: Some(0)
+        }
+    }
+
+    /// Rewind the generator to the start of its current sub-stream,
+    /// so that it replays the outputs it has produced since it was
+    /// constructed, split or called from a PRF.  This is the same as
+    /// `advance_to(0)`.
+    pub fn reset_counter(&mut self) {
+        self.advance_to(0);
+    }
+
+    /// Capture the whole state of the generator, so that `restore`
+    /// can later rewind it, or another generator, to this point.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            v:    [self.v0, self.v1, self.v2, self.v3],
+            ctr:  self.ctr,
+            len:  self.len,
+            half: self.half,
+            tail: self.tail
         }
     }
 
+    /// Put the generator back in the state captured by `checkpoint`.
+    /// It then produces the same outputs and splits as the
+    /// checkpointed generator did.
+    pub fn restore(&mut self, checkpoint: &Checkpoint) {
+        let [v0, v1, v2, v3] = checkpoint.v;
+        self.v0 = v0;
+        self.v1 = v1;
+        self.v2 = v2;
+        self.v3 = v3;
+        self.ctr = checkpoint.ctr;
+        self.len = checkpoint.len;
+        self.half = checkpoint.half;
+        self.tail = checkpoint.tail;
+    }
+
     fn clone(&self) -> SipRng {
         SipRng { 
             v0:  self.v0,
@@ -123,10 +430,159 @@ impl SipRng {
             v2:  self.v2,
             v3:  self.v3,
             ctr: self.ctr,
-            len: self.len
+            len: self.len,
+            half: self.half,
+            tail: self.tail
         }
     }
 
+    /// The number of branches that this generator has descended into
+    /// since it was keyed, saturating at `MAX_DEPTH`.
+    pub fn depth(&self) -> u32 {
+        (self.len / 2).min(MAX_DEPTH as u64) as u32
+    }
+
+    /// Return the generator for branch `i` of this one, as a PRF
+    /// split off it would, or an error if this generator's `depth` has
+    /// saturated at `MAX_DEPTH`.  The check is purely nominal, for
+    /// callers that want to keep depths within what `depth` can
+    /// report; only debug builds assert the same limit when
+    /// splitting.
+    pub fn descend_checked(&self, i: u32) -> Result<SipRng, DepthError> {
+        if self.len / 2 >= MAX_DEPTH as u64 {
+            return Err(DepthError);
+        }
+        let mut child = self.clone();
+        child.descend(i);
+        Ok(child)
+    }
+
+    /// Return a generator for the domain named by `tag`, for when
+    /// several components derive their generators from the same
+    /// parent and must not collide even if they then split and call
+    /// PRFs with the same branch numbers.  Generators returned for
+    /// different tags are independent; the same tag always gives the
+    /// same generator.  This counts as one level of `depth` and leaves
+    /// this generator alone.
+    pub fn with_context(&self, tag: u64) -> SipRng {
+        let mut child = self.clone();
+        sip_block!(child.v0, child.v1, child.v2, child.v3, child.ctr as u64);
+        sip_block!(child.v0, child.v1, child.v2, child.v3, CONTEXT_BLOCK);
+        sip_block!(child.v0, child.v1, child.v2, child.v3, tag);
+        child.len = child.len.wrapping_add(2);
+        child.ctr = 0;
+        child.half = None;
+        child.tail = None;
+        child
+    }
+
+    /// Return a generator for the domain named by `label`, like
+    /// `with_context` does for a numeric tag.  Generators returned for
+    /// different labels are independent of each other and of those
+    /// returned by `with_context`; the same label always gives the
+    /// same generator.  This leaves this generator alone.
+    pub fn fork_labeled(&self, label: &str) -> SipRng {
+        self.fork_bytes(label.as_bytes())
+    }
+
+    /// Return a generator for the domain named by the bytes `label`,
+    /// such as a serialized key.  `fork_labeled` is this on the UTF-8
+    /// bytes of a string.  The label is absorbed whole, along with its
+    /// length, rather than reduced to a branch number, so different
+    /// labels give colliding generators only by the chance collisions
+    /// of SipHash.  The label is not a secret: anyone who knows this
+    /// generator's state and the label can derive the same generator.
+    /// This leaves this generator alone.
+    pub fn fork_bytes(&self, label: &[u8]) -> SipRng {
+        let mut child = self.clone();
+        child.absorb_label(label);
+        child
+    }
+
+    /// Call `fork_labeled` for each of `labels`, and collect the
+    /// generators by label.
+    pub fn forks<'a, I>(&self, labels: I) -> HashMap<String, SipRng>
+        where I: IntoIterator<Item=&'a str>
+    {
+        labels.into_iter()
+            .map(|label| (label.to_string(), self.fork_labeled(label)))
+            .collect()
+    }
+
+    /// Record a label block, the length of `label` and its bytes, in
+    /// little-endian words padded with zeroes, and start a new
+    /// sub-stream.  This counts as one descend.
+    fn absorb_label(&mut self, label: &[u8]) {
+        sip_block!(self.v0, self.v1, self.v2, self.v3, self.ctr as u64);
+        sip_block!(self.v0, self.v1, self.v2, self.v3, LABEL_BLOCK);
+        sip_block!(self.v0, self.v1, self.v2, self.v3, label.len() as u64);
+        for word in label_words(label) {
+            sip_block!(self.v0, self.v1, self.v2, self.v3, word);
+        }
+        self.len = self.len.wrapping_add(2);
+        self.ctr = 0;
+        self.half = None;
+        self.tail = None;
+    }
+
+    /// Return a 128-bit summary of the generator's whole state: its
+    /// key, its depth, its position in the current sub-stream and any
+    /// output buffered by `next_u32` or `read`.  This is meant for logging where a
+    /// generator was, not for restoring it.  Two generators with equal
+    /// fingerprints are in the same state with overwhelming
+    /// probability, and the fingerprint of a given state does not
+    /// change between runs or platforms.
+    pub fn fingerprint(&self) -> u128 {
+        let (hi, lo) = self.hash_state(&[]);
+        (hi as u128) << 64 | lo as u128
+    }
+
+    /// Create a generator keyed from the whole states of `a` and `b`,
+    /// so that its output depends on both: for example, on a run's
+    /// seed and on a task's.  The result is independent of both
+    /// inputs, and the order of the arguments matters: `combine(a, b)`
+    /// and `combine(b, a)` are unrelated generators.  Neither input is
+    /// advanced.
+    pub fn combine(a: &SipRng, b: &SipRng) -> SipRng {
+        let (b_hi, b_lo) = b.hash_state(&[]);
+        let (k0, k1) = a.hash_state(&[COMBINE_BLOCK, b_hi, b_lo]);
+        SipRng::new(k0, k1)
+    }
+
+    /// Hash the whole state of the generator, followed by `blocks`,
+    /// into two words.
+    fn hash_state(&self, blocks: &[u64]) -> (u64, u64) {
+        let (mut v0, mut v1, mut v2, mut v3) =
+            (self.v0, self.v1, self.v2, self.v3);
+        let half = match self.half {
+            Some(half) => (1 << 32) | half as u64,
+            None => 0
+        };
+        let tail = match self.tail {
+            Some((bytes, n)) => (n as u64) << 56 | bytes,
+            None => 0
+        };
+        sip_block!(v0, v1, v2, v3, self.ctr as u64);
+        sip_block!(v0, v1, v2, v3, self.len);
+        sip_block!(v0, v1, v2, v3, half);
+        sip_block!(v0, v1, v2, v3, tail);
+        for &block in blocks {
+            sip_block!(v0, v1, v2, v3, block);
+        }
+
+        let (mut w0, mut w1, mut w2, mut w3) = (v0, v1 ^ 0xee, v2, v3);
+        let len = 8 * (4 + blocks.len());
+        let lo = sip_finish!(v0, v1, v2, v3, len);
+        let hi = sip_finish!(w0, w1, w2, w3, len);
+        (hi, lo)
+    }
+
+    /// Run a `health::health_check` on the outputs that this
+    /// generator would produce next, without advancing it.
+    pub fn health_check(&self) -> Result<(), HealthError> {
+        health::health_check(&mut self.clone())
+    }
+
 
     /*
      * The generator works by encoding execution traces as two kinds
@@ -141,45 +597,189 @@ impl SipRng {
      * its least significant bits, and all zeroes in the most
      * significant bits.  A split block is an u64 that encodes an u32
      * branch number in its LSBs, and all ones in its MSBs.
+     *
+     * `with_context` records a counter block followed by a **context
+     * block**, whose MSBs are all ones but the last and whose LSBs
+     * are zero, and then the tag as a whole block.  The context block
+     * is distinct from both other kinds, so the trace stays
+     * unambiguous whatever the tag is.  `fork_labeled` does the same
+     * with a **label block**, followed by the label's length and
+     * then its bytes.
+     *
+     * `combine` and `with_key_and_domain` don't extend the trace:
+     * they hash a whole state, followed by a **combine block** or a
+     * **domain block** and their input, into the key of a new root.
+     *
+     * An output is the standard SipHash finalization of the trace,
+     * the current counter block and then the trace's length as a
+     * whole block, so the full depth counts and not just the low
+     * byte that SipHash keeps of the length.
      */
 
 
-    /// Generate one block of sequential output.
+    /// Return the output that the next call to `next_u64` will
+    /// produce, without advancing the generator.  Repeated calls
+    /// return the same value until the generator is drawn from.
     #[inline]
-    fn advance(&mut self) -> u64 {
-        let result: u64 = {
-            // Compute a hash result.  This doesn't mutate the
-            // generator state.
-            let (mut v0, mut v1, mut v2, mut v3) = 
-                (self.v0, self.v1, self.v2, self.v3);
-            sip_block!(v0, v1, v2, v3, self.ctr as u64);
-            sip_finish!(v0, v1, v2, v3, (self.len + 1).wrapping_mul(8))
-        };
+    pub fn peek(&self) -> u64 {
+        // Compute a hash result.  This doesn't mutate the generator
+        // state.
+        let (mut v0, mut v1, mut v2, mut v3) = 
+            (self.v0, self.v1, self.v2, self.v3);
+        sip_block!(v0, v1, v2, v3, self.ctr as u64);
+        sip_block!(v0, v1, v2, v3, self.len);
+        sip_finish!(v0, v1, v2, v3, self.len.wrapping_add(2).wrapping_mul(8))
+    }
 
-        self.ctr = if self.ctr == u32::MAX {
-            // We're about to overflow the counter.  We avoid a
-            // cycle by descending into a branch.
-            self.descend(0);
-            0
-        } else {
-            self.ctr.wrapping_add(1)
-        };
-         
-        result
+    unstable_fn! {
+        /// Generate one block of sequential output: return the hash
+        /// of the trace so far followed by the current counter block,
+        /// and increment the counter.  If the counter is about to
+        /// overflow, descend into branch 0 instead, so the generator
+        /// never cycles.  This is what `next_u64` does.
+        ///
+        /// Public only with the `unstable` feature.
+        #[inline]
+        fn advance(&mut self) -> u64 {
+            let result = self.peek();
+
+            self.ctr = if self.ctr == u32::MAX {
+                // We're about to overflow the counter.  We avoid a
+                // cycle by descending into a branch.
+                self.descend(0);
+                0
+            } else {
+                self.ctr.wrapping_add(1)
+            };
+             
+            result
+        }
+    }
+
+    unstable_fn! {
+        /// "Descend" into a numbered branch: absorb the counter block
+        /// and then the split block for branch `i`, count the two
+        /// blocks in the length, and start a new sub-stream with the
+        /// counter at zero.  `call(i)` on a PRF is a copy of the PRF's
+        /// generator that has descended into `i`; `split` descends the
+        /// parent into 0 and the child into 1.
+        ///
+        /// Public only with the `unstable` feature.  Schemes built on
+        /// it must never let two generators reach the same sequence of
+        /// counter values and branches, or they will produce the same
+        /// output.
+        /// Branch numbers are `u32` because split blocks keep their
+        /// top 32 bits set to tell them apart from counter blocks.
+        #[inline]
+        fn descend(&mut self, i: u32) {
+            debug_assert!(self.len / 2 < MAX_DEPTH as u64,
+                          "split depth would exceed MAX_DEPTH");
+            sip_block!(self.v0, self.v1, self.v2, self.v3, self.ctr as u64);
+            sip_block!(self.v0, self.v1, self.v2, self.v3, 
+                       (i as u64) | 0xffff_ffff_0000_0000);
+            self.len = self.len.wrapping_add(2);
+            self.ctr = 0;
+            self.half = None;
+            self.tail = None;
+        }
+    }
+
+}
+
+impl fmt::Display for DepthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "split depth would exceed {}", MAX_DEPTH)
+    }
+}
+
+impl Error for DepthError {}
+
+impl fmt::Display for SipRngError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SipRngError::Entropy(ref err) =>
+                write!(f, "could not obtain entropy: {}", err),
+        }
+    }
+}
+
+impl Error for SipRngError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            SipRngError::Entropy(ref err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for SipRngError {
+    fn from(err: io::Error) -> SipRngError {
+        SipRngError::Entropy(err)
+    }
+}
+
+
+thread_local!(
+    static THREAD_SIP_RNG: Rc<RefCell<SipRng>> = {
+        let rng = SipRng::try_from_entropy().unwrap_or_else(|err| {
+            panic!("could not initialize split_thread_rng: {}", err)
+        });
+        Rc::new(RefCell::new(rng))
+    }
+);
+
+/// Return a handle to a `SipRng` that is local to the current thread
+/// and keyed from the operating system's entropy the first time it is
+/// used.  This is the splittable analogue of `rand::thread_rng`, for
+/// casual use where splitting is wanted but reproducibility isn't:
+/// all handles in a thread share the same generator, and it gets a
+/// different key on every run.
+pub fn split_thread_rng() -> SplitThreadRng {
+    SplitThreadRng {
+        rng: THREAD_SIP_RNG.with(|rng| rng.clone())
+    }
+}
+
+impl SplitThreadRng {
+    /// Split a new generator off the thread-local one.
+    pub fn split(&mut self) -> SipRng {
+        self.rng.borrow_mut().split()
     }
 
-    /// "Descend" into a numbered branch.
+    /// Split a pseudo-random function off the thread-local generator.
+    pub fn splitn(&mut self) -> SipPrf {
+        self.rng.borrow_mut().splitn()
+    }
+}
+
+impl Rng for SplitThreadRng {
     #[inline]
-    fn descend(&mut self, i: u32) {
-        sip_block!(self.v0, self.v1, self.v2, self.v3, self.ctr as u64);
-        sip_block!(self.v0, self.v1, self.v2, self.v3, 
-                   (i as u64) | 0xffff_ffff_0000_0000);
-        self.len = self.len.wrapping_add(2);
-        self.ctr = 0;
+    fn next_u32(&mut self) -> u32 {
+        self.rng.borrow_mut().next_u32()
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.rng.borrow_mut().next_u64()
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.borrow_mut().fill_bytes(dest)
     }
+}
 
+
+impl Iterator for SequentialSplits {
+    type Item = SipRng;
+
+    fn next(&mut self) -> Option<SipRng> {
+        let i = self.next?;
+        self.next = i.checked_add(1);
+        Some(self.prf.call(i))
+    }
 }
 
+
 impl SplitPrf<SipRng> for SipPrf {
     fn call(&self, i: u32) -> SipRng {
         let mut r = self.0.clone();
@@ -188,6 +788,52 @@ impl SplitPrf<SipRng> for SipPrf {
     }
 }
 
+impl SipPrf {
+    /// Return the generator at the end of `path` in the tree of PRFs
+    /// rooted at this one: `call_path(&[i, j])` is `call(i).prf()
+    /// .call(j)`, and so on.  An empty path gives the PRF's own
+    /// generator, which is the one that `call` descends from.
+    pub fn call_path(&self, path: &[u32]) -> SipRng {
+        let mut r = self.0.clone();
+        for &i in path {
+            r.descend(i);
+        }
+        r
+    }
+
+    /// Return an endless iterator over the generators of the tree in
+    /// which every node has `branching` children, in breadth-first
+    /// order: `call(0)` to `call(branching - 1)`, then the children of
+    /// `call(0)`, and so on.  The node at path `p` is `call_path(p)`.
+    /// The iterator is empty if `branching` is zero.
+    pub fn breadth_first(&self, branching: u32) -> BreadthFirst {
+        BreadthFirst {
+            branching,
+            parent: SipPrf(self.0.clone()),
+            next:   0,
+            queue:  VecDeque::new()
+        }
+    }
+}
+
+impl Iterator for BreadthFirst {
+    type Item = SipRng;
+
+    fn next(&mut self) -> Option<SipRng> {
+        if self.branching == 0 {
+            return None;
+        }
+        if self.next == self.branching {
+            self.parent = SipPrf(self.queue.pop_front()?);
+            self.next = 0;
+        }
+        let child = self.parent.call(self.next);
+        self.next += 1;
+        self.queue.push_back(child.clone());
+        Some(child)
+    }
+}
+
 impl SplitRng for SipRng {
     type Prf = SipPrf;
 
@@ -213,22 +859,100 @@ impl Rng for SipRng {
     
     #[inline]
     fn next_u32(&mut self) -> u32 {
-        self.next_u64() as u32
+        match self.half.take() {
+            Some(half) => half,
+            None => {
+                let block = self.next_u64();
+                self.half = Some((block >> 32) as u32);
+                block as u32
+            }
+        }
     }
     
+    /// Fill `dest` with the bytes of successive `next_u64` outputs, in
+    /// little-endian order on every platform.  A partial last chunk
+    /// takes the low bytes of a whole output and discards the rest, so
+    /// this consumes `dest.len().div_ceil(8)` outputs; an empty `dest`
+    /// consumes none.
     #[inline]
     fn fill_bytes(&mut self, dest: &mut [u8]) {
         for chunk in dest.chunks_mut(8) {
-            let block = unsafe {
-                mem::transmute::<u64, [u8; 8]>(self.next_u64())
+            let block = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+    }
+}
+
+/// Reads return the bytes of successive `next_u64` outputs in
+/// little-endian order, like `fill_bytes`, but keep the unread bytes of
+/// a read's last output for the next read.  So the byte stream doesn't
+/// depend on how it is split into reads.  Reads never fail and always
+/// fill the whole buffer.
+impl Read for SipRng {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut rest = &mut buf[..];
+        while !rest.is_empty() {
+            let (bytes, n) = match self.tail.take() {
+                Some(tail) => tail,
+                None => (self.next_u64(), 8)
             };
-            for i in 0..chunk.len() {
-                chunk[i] = block[i];
+            let k = rest.len().min(n as usize);
+            rest[..k].copy_from_slice(&bytes.to_le_bytes()[..k]);
+            if k < n as usize {
+                self.tail = Some((bytes >> (8 * k), n - k as u8));
             }
+            rest = &mut rest[k..];
+        }
+        Ok(buf.len())
+    }
+}
+
+/// Generates blocks of eight consecutive `next_u64` outputs, so that a
+/// `SipRng` can be wrapped in `rand_core::block::BlockRng64`.
+#[cfg(feature = "rand-upgrade")]
+impl ::rand_core::block::BlockRngCore for SipRng {
+    type Item = u64;
+    type Results = [u64; 8];
+
+    fn generate(&mut self, results: &mut [u64; 8]) {
+        for result in results.iter_mut() {
+            *result = self.advance();
         }
     }
 }
 
+/// The `rand_core` generator interface, with the same output as the
+/// `rand` 0.4 one.  This is what the `Fill` impls of newer `rand`
+/// versions fill slices and arrays through, so a slice of `u32`s is
+/// filled with the same values as successive `next_u32` calls.
+///
+/// With both `rand::Rng` and `rand_core::RngCore` in scope, calls to
+/// the methods they share are ambiguous, and need spelling out as
+/// `Rng::next_u64(&mut rng)`.
+#[cfg(feature = "rand-upgrade")]
+impl ::rand_core::RngCore for SipRng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        Rng::next_u32(self)
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        Rng::next_u64(self)
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        Rng::fill_bytes(self, dest)
+    }
+
+    #[inline]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ::rand_core::Error> {
+        Rng::fill_bytes(self, dest);
+        Ok(())
+    }
+}
+
 impl SeedableRng<(u64, u64)> for SipRng {
     
     fn reseed(&mut self, seed: (u64, u64)) {
@@ -238,6 +962,8 @@ impl SeedableRng<(u64, u64)> for SipRng {
         self.v3 = seed.1 ^ C3;
         self.len = 0;
         self.ctr = 0;
+        self.half = None;
+        self.tail = None;
     }
     
     fn from_seed(seed: (u64, u64)) -> SipRng {
@@ -246,10 +972,49 @@ impl SeedableRng<(u64, u64)> for SipRng {
     }
 }
 
+impl SeedableRng<u64> for SipRng {
+
+    fn reseed(&mut self, seed: u64) {
+        self.reseed(expand_seed(seed));
+    }
+
+    fn from_seed(seed: u64) -> SipRng {
+        SipRng::from_u64(seed)
+    }
+}
+
+/// The bytes of `label` in little-endian words, the last one padded
+/// with zeroes.  This is how both `fork_labeled` and
+/// `with_key_and_domain` absorb a label.
+fn label_words<'a>(label: &'a [u8]) -> impl Iterator<Item=u64> + 'a {
+    label.chunks(8).map(|chunk| {
+        let mut word = [0u8; 8];
+        word[..chunk.len()].copy_from_slice(chunk);
+        u64::from_le_bytes(word)
+    })
+}
+
+/// Expand a one-word seed into a two-word key, using the output
+/// function of the SplitMix64 generator.
+fn expand_seed(seed: u64) -> (u64, u64) {
+    let mut state = seed;
+    let k0 = splitmix64(&mut state);
+    let k1 = splitmix64(&mut state);
+    (k0, k1)
+}
+
+#[inline]
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
 impl Rand for SipRng {
     fn rand<R: Rng>(other: &mut R) -> SipRng {
-        let (k0, k1) = other.gen::<(u64, u64)>();
-        SipRng::new(k0, k1)
+        SipRng::from_rng(other)
     }
 }
 
@@ -277,33 +1042,849 @@ mod tests {
         ::tests::test_split_rand_closure(&mut gen_siprng());
     }
 
+    #[test]
+    fn test_split_rand_fallible_closure() {
+        ::tests::test_split_rand_fallible_closure(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_rand_closure_threads() {
+        ::tests::test_split_rand_closure_threads(&mut gen_siprng());
+    }
+
     #[test]
     fn test_split_rand_split() {
         ::tests::test_split_rand_split(&mut gen_siprng());
     }
 
+    #[test]
+    fn test_split_map() {
+        ::tests::test_split_map(&mut gen_siprng());
+    }
 
-    fn gen_seed() -> (u64, u64) {
-        let mut osrng = OsRng::new().ok().expect("Could not create OsRng");
-        osrng.gen()
+    #[test]
+    fn test_split2() {
+        ::tests::test_split2(&mut gen_siprng());
     }
 
     #[test]
-    fn test_rng_rand_seeded() {
-        let seed = gen_seed();
-        ::tests::test_rng_rand_seeded::<SipRng, (u64, u64)>(seed);
+    fn test_split_sample_iter() {
+        ::tests::test_split_sample_iter(&mut gen_siprng());
     }
 
     #[test]
-    fn test_rng_seeded() {
-        let seed = gen_seed();
-        ::tests::test_rng_seeded::<SipRng, (u64, u64)>(seed);
+    fn test_split_permutation() {
+        ::tests::test_split_permutation(&mut gen_siprng());
     }
 
     #[test]
-    fn test_rng_reseed() {
-        let seed = gen_seed();
-        ::tests::test_rng_reseed::<SipRng, (u64, u64)>(seed);
+    fn test_split_assoc_list() {
+        ::tests::test_split_assoc_list(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_partition() {
+        ::tests::test_split_partition(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_table() {
+        ::tests::test_split_table(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_many() {
+        ::tests::test_split_many(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_zip_gen() {
+        ::tests::test_zip_gen(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_gen_timestamps() {
+        ::tests::test_split_gen_timestamps(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_grid() {
+        ::tests::test_split_grid(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_map_filter_gen() {
+        ::tests::test_split_map_filter_gen(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_map_exact() {
+        ::tests::test_split_map_exact(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_markov_step() {
+        ::tests::test_split_markov_step(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_build_hasher() {
+        ::tests::test_split_build_hasher(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_rand_closure_tuple() {
+        ::tests::test_split_rand_closure_tuple(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_fold() {
+        ::tests::test_split_fold(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_generic_consumer() {
+        ::tests::test_generic_consumer(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_gen_bitset() {
+        ::tests::test_split_gen_bitset(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_gen_subset() {
+        ::tests::test_split_gen_subset(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_gen_uuid() {
+        ::tests::test_split_gen_uuid(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_gen_normal() {
+        ::tests::test_split_gen_normal(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_gen_bools() {
+        ::tests::test_split_gen_bools(&mut gen_siprng());
+    }
+
+    #[test]
+    fn test_split_gen_entry_points() {
+        ::tests::test_split_gen_entry_points(&mut gen_siprng());
+    }
+
+
+    /// Check the SipHash macros against the reference SipHash-2-4
+    /// outputs for the empty message and for the 8-byte message
+    /// `00 01 .. 07`.
+    #[test]
+    fn test_siphash_vector() {
+        let rng = SipRng::new(0x0706050403020100, 0x0f0e0d0c0b0a0908);
+        let (mut v0, mut v1, mut v2, mut v3) = (rng.v0, rng.v1, rng.v2, rng.v3);
+        let hash = sip_finish!(v0, v1, v2, v3, 0u8);
+        assert_eq!(hash, 0x726fdb47dd0e0e31);
+
+        let (mut v0, mut v1, mut v2, mut v3) = (rng.v0, rng.v1, rng.v2, rng.v3);
+        sip_block!(v0, v1, v2, v3, 0x0706050403020100);
+        let hash = sip_finish!(v0, v1, v2, v3, 8u8);
+        assert_eq!(hash, 0x93f5f5799a932462);
+    }
+
+
+    #[test]
+    fn test_descend_distinct_states() {
+        use std::collections::HashSet;
+
+        let parent = gen_siprng();
+        let fingerprints: HashSet<u128> = (0..4096).map(|i| {
+            let mut child = parent.clone();
+            child.descend(i);
+            child.fingerprint()
+        }).collect();
+        assert_eq!(fingerprints.len(), 4096);
+    }
+
+
+    #[test]
+    fn test_fingerprint() {
+        use std::collections::HashSet;
+
+        let parent = gen_siprng();
+        assert_eq!(parent.fingerprint(), parent.clone().fingerprint());
+
+        let mut fingerprints = HashSet::new();
+        for i in 0..1024 {
+            let mut child = parent.clone();
+            child.descend(i);
+            assert!(fingerprints.insert(child.fingerprint()));
+            for _ in 0..4 {
+                child.next_u32();
+                assert!(fingerprints.insert(child.fingerprint()));
+            }
+        }
+    }
+
+
+    #[test]
+    fn test_combine() {
+        let (k0, k1) = gen_seed();
+        let a = SipRng::new(k0, k1);
+        let b = SipRng::new(k1, k0);
+
+        let ab = SipRng::combine(&a, &b);
+        let x = ab.clone().next_u64();
+        assert_eq!(x, SipRng::combine(&a, &b).next_u64());
+        assert!(x != a.clone().next_u64());
+        assert!(x != b.clone().next_u64());
+        assert!(x != SipRng::combine(&b, &a).next_u64());
+
+        let mut c = b.clone();
+        c.next_u64();
+        assert!(x != SipRng::combine(&a, &c).next_u64());
+        assert!(ab.fingerprint() != a.fingerprint());
+    }
+
+
+    #[test]
+    fn test_with_context() {
+        use ::{SplitRng, SplitPrf};
+
+        let rng = gen_siprng();
+        let pa = rng.with_context(1).splitn();
+        let pb = rng.with_context(2).splitn();
+        let pc = rng.with_context(1).splitn();
+        for i in 0..100 {
+            let (mut ra, mut rb, mut rc) = (pa.call(i), pb.call(i), pc.call(i));
+            let a: [u64; 4] = ra.gen();
+            let b: [u64; 4] = rb.gen();
+            let c: [u64; 4] = rc.gen();
+            assert!(a != b);
+            assert_eq!(a, c);
+        }
+
+        let mut child = rng.clone();
+        child.descend(0);
+        assert!(child.clone().next_u64() != rng.with_context(0).next_u64());
+        assert!(rng.with_context(0xffff_ffff_0000_0000).fingerprint()
+                != child.fingerprint());
+    }
+
+
+    #[test]
+    fn test_read() {
+        use std::io::Read;
+
+        let mut reader = gen_siprng();
+        let mut direct = reader.clone();
+        let mut read = [0u8; 96];
+        let mut filled = [0u8; 96];
+        reader.read_exact(&mut read).unwrap();
+        direct.fill_bytes(&mut filled);
+        assert_eq!(read[..], filled[..]);
+
+        // A partial read keeps the rest of its last word for the
+        // next read.
+        assert_eq!(reader.read(&mut read[..13]).unwrap(), 13);
+        assert_eq!(reader.read(&mut read[13..16]).unwrap(), 3);
+        direct.fill_bytes(&mut filled[..16]);
+        assert_eq!(read[..16], filled[..16]);
+        assert_eq!(reader.next_u64(), direct.next_u64());
+    }
+
+    #[test]
+    fn test_read_chunks() {
+        use std::io::Read;
+
+        let mut whole = gen_siprng();
+        let mut chunked = whole.clone();
+        let mut expected = [0u8; 100];
+        whole.read_exact(&mut expected).unwrap();
+        let mut read = [0u8; 100];
+        for chunk in read.chunks_mut(3) {
+            assert_eq!(chunked.read(chunk).unwrap(), chunk.len());
+        }
+        assert_eq!(read[..], expected[..]);
+        assert_eq!(chunked.checkpoint(), whole.checkpoint());
+    }
+
+    #[test]
+    fn test_fill_bytes_tail() {
+        let seed = gen_seed();
+        for &len in &[0, 1, 7, 8, 9] {
+            let mut rng = SipRng::new(seed.0, seed.1);
+            let mut dest = vec![0u8; len];
+            rng.try_fill_bytes(&mut dest).unwrap();
+
+            let mut words = SipRng::new(seed.0, seed.1);
+            let expected: Vec<u8> = (0..len.div_ceil(8))
+                .flat_map(|_| words.next_u64().to_le_bytes().to_vec())
+                .take(len)
+                .collect();
+            assert_eq!(dest, expected);
+            assert_eq!(rng.next_u64(), words.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_restore() {
+        use ::SplitRng;
+
+        let mut rng = gen_siprng();
+        rng.split();
+        rng.next_u32();
+        let checkpoint = rng.checkpoint();
+        let continuation: Vec<u32> = rng.gen_iter().take(9).collect();
+        let child = rng.split().next_u64();
+
+        rng.next_u64();
+        rng.split();
+        rng.restore(&checkpoint);
+        assert_eq!(rng.checkpoint(), checkpoint);
+        let replay: Vec<u32> = rng.gen_iter().take(9).collect();
+        assert_eq!(replay, continuation);
+        assert_eq!(rng.split().next_u64(), child);
+
+        let mut other = SipRng::new(0, 0);
+        other.restore(&checkpoint);
+        assert_eq!(other.gen_iter().take(9).collect::<Vec<u32>>(), continuation);
+    }
+
+    #[test]
+    fn test_seq_tuple() {
+        use ::{SeqTuple, SplitRng};
+
+        let seed = gen_seed();
+        let mut ra = SipRng::new(seed.0, seed.1);
+        let mut rb = SipRng::new(seed.0, seed.1);
+        let SeqTuple(a) = ra.split_gen::<SeqTuple<(u64, bool, u32)>>();
+        let SeqTuple(b) = rb.split_gen::<SeqTuple<(u64, bool, u32)>>();
+        assert_eq!(a, b);
+
+        // The elements come from one split, in order.
+        let mut sub = SipRng::new(seed.0, seed.1).split();
+        assert_eq!(a, (sub.gen(), sub.gen(), sub.gen()));
+
+        // One descend, against one per element for the plain tuple.
+        let _: (u64, bool, u32) = rb.split_gen();
+        assert_eq!(ra.depth(), 1);
+        assert_eq!(rb.depth(), 4);
+    }
+
+    #[test]
+    fn test_forks() {
+        let rng = gen_siprng();
+        let labels = ["physics", "ai", "audio", "", "ai2"];
+        let forward = rng.forks(labels.iter().cloned());
+        let backward = rng.forks(labels.iter().rev().cloned());
+        assert_eq!(forward.len(), labels.len());
+        for label in labels.iter() {
+            let direct = rng.fork_labeled(label);
+            let a: Vec<u64> = forward[*label].clone().gen_iter().take(8).collect();
+            let b: Vec<u64> = backward[*label].clone().gen_iter().take(8).collect();
+            let c: Vec<u64> = direct.clone().gen_iter().take(8).collect();
+            assert_eq!(a, c);
+            assert_eq!(b, c);
+        }
+        assert!(forward["ai"].clone().next_u64() != forward["ai2"].clone().next_u64());
+        assert!(rng.fork_labeled("\0").next_u64() != rng.fork_labeled("").next_u64());
+        assert!(rng.fork_labeled("").next_u64() != rng.with_context(0).next_u64());
+    }
+
+
+    #[test]
+    fn test_with_key_and_domain() {
+        let (k0, k1) = gen_seed();
+        let cache = SipRng::with_key_and_domain(k0, k1, "cache");
+        let again = SipRng::with_key_and_domain(k0, k1, "cache");
+        let session = SipRng::with_key_and_domain(k0, k1, "session");
+        let a: Vec<u64> = cache.clone().gen_iter().take(8).collect();
+        let b: Vec<u64> = again.clone().gen_iter().take(8).collect();
+        let c: Vec<u64> = session.clone().gen_iter().take(8).collect();
+        assert_eq!(a, b);
+        assert!(a.iter().zip(c.iter()).all(|(x, y)| x != y));
+
+        assert_eq!(cache.depth(), 0);
+        assert!(cache.root_key().is_some());
+        let master = SipRng::new(k0, k1);
+        assert!(a[0] != master.clone().next_u64());
+        assert!(a[0] != master.fork_labeled("cache").next_u64());
+        assert!(a[0] != SipRng::with_key_and_domain(k0, k1, "").next_u64());
+        assert!(a[0] != SipRng::with_key_and_domain(k0 ^ 1, k1, "cache").next_u64());
+    }
+
+    #[test]
+    fn test_fork_bytes() {
+        let rng = gen_siprng();
+        let label = [0x12u8, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0xff];
+        let a: Vec<u64> = rng.fork_bytes(&label).gen_iter().take(8).collect();
+        let b: Vec<u64> = rng.fork_bytes(&label).gen_iter().take(8).collect();
+        assert_eq!(a, b);
+        for i in 0..label.len() {
+            let mut changed = label;
+            changed[i] ^= 1;
+            assert!(rng.fork_bytes(&changed).next_u64() != a[0]);
+        }
+        assert!(rng.fork_bytes(&label[..8]).next_u64() != a[0]);
+        assert_eq!(rng.fork_bytes(b"ai").next_u64(), rng.fork_labeled("ai").next_u64());
+    }
+
+
+    #[test]
+    fn test_descend_checked() {
+        use super::{DepthError, MAX_DEPTH};
+
+        let mut rng = gen_siprng();
+        for i in 0..100 {
+            assert_eq!(rng.depth(), i);
+            let mut checked = rng.descend_checked(i).unwrap();
+            rng.descend(i);
+            assert_eq!(checked.next_u64(), rng.clone().next_u64());
+        }
+
+        // Descending billions of times would take too long.
+        rng.len = 2 * (MAX_DEPTH as u64 - 3);
+        for i in 0..3 {
+            assert!(rng.descend_checked(i).is_ok());
+            rng.descend(i);
+        }
+        assert_eq!(rng.depth(), MAX_DEPTH);
+        assert_eq!(rng.descend_checked(0).err(), Some(DepthError));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn test_descend_past_max_depth() {
+        use super::MAX_DEPTH;
+
+        let mut rng = gen_siprng();
+        rng.len = 2 * MAX_DEPTH as u64;
+        rng.descend(0);
+    }
+
+    #[test]
+    fn test_deep_branches_independent() {
+        // Depths that differ by a multiple of 256 used to share the
+        // low byte of the length that finalization absorbs.
+        let (k0, k1) = gen_seed();
+        let (mut shallow, mut deep) = (SipRng::new(k0, k1), SipRng::new(k0, k1));
+        for _ in 0..5 {
+            shallow.descend(0);
+        }
+        for _ in 0..261 {
+            deep.descend(0);
+        }
+        assert_eq!((shallow.depth(), deep.depth()), (5, 261));
+
+        let n = 1000;
+        let mut differing_bits = 0;
+        for _ in 0..n {
+            let (a, b) = (shallow.next_u64(), deep.next_u64());
+            assert!(a != b);
+            differing_bits += (a ^ b).count_ones();
+        }
+        // 64000 coin flips: the mean is 32000, the deviation 126.
+        assert!((differing_bits as i64 - 32 * n).abs() < 1000);
+    }
+
+
+    #[cfg(feature = "rand-upgrade")]
+    #[test]
+    fn test_block_rng_core() {
+        use rand_core::RngCore;
+        use rand_core::block::BlockRng64;
+
+        let rng = gen_siprng();
+        let mut block = BlockRng64::new(rng.clone());
+        let mut serial = rng.clone();
+        for _ in 0..100 {
+            assert_eq!(block.next_u64(), Rng::next_u64(&mut serial));
+        }
+    }
+
+
+    #[cfg(feature = "rand-upgrade")]
+    #[test]
+    fn test_rng_core_fill() {
+        use rand_core::RngCore;
+
+        // What `Fill` does for a `[u32; 16]`: fill its bytes, then read
+        // them as little-endian words.
+        let mut rng = gen_siprng();
+        let mut serial = rng.clone();
+        let mut bytes = [0u8; 64];
+        RngCore::fill_bytes(&mut rng, &mut bytes);
+        for chunk in bytes.chunks(4) {
+            let word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            assert_eq!(word, Rng::next_u32(&mut serial));
+        }
+
+        let mut rng = gen_siprng();
+        let mut serial = rng.clone();
+        let mut dest = [0u8; 40];
+        let mut expected = [0u8; 40];
+        RngCore::try_fill_bytes(&mut rng, &mut dest).unwrap();
+        Rng::fill_bytes(&mut serial, &mut expected);
+        assert_eq!(dest, expected);
+        assert_eq!(RngCore::next_u64(&mut rng), Rng::next_u64(&mut serial));
+    }
+
+
+    #[test]
+    fn test_prf_fan_out() {
+        use std::collections::HashSet;
+        use ::{SplitRng, SplitPrf};
+
+        let prf = gen_siprng().splitn();
+        let n = 1 << 20;
+        let mut fingerprints = HashSet::with_capacity(n);
+        for i in 0..n as u32 {
+            assert!(fingerprints.insert(prf.call(i).fingerprint()),
+                    "branch {} collides with an earlier one", i);
+        }
+        assert!(fingerprints.insert(prf.call(u32::MAX).fingerprint()));
+    }
+
+
+    #[test]
+    fn test_reseed_with_position() {
+        use ::{SplitRng, SplitPrf};
+
+        let mut natural = SipRng::new(3, 4);
+        for _ in 0..3 {
+            natural.next_u64();
+        }
+        let mut natural = natural.split().splitn().call(7);
+        natural.split();
+        for _ in 0..5 {
+            natural.next_u64();
+        }
+
+        let mut resumed = SipRng::new(5, 6);
+        resumed.next_u32();
+        resumed.reseed_with_position(3, 4, &[(3, 1), (0, 1), (0, 7), (0, 0)], 5);
+        assert_eq!(resumed.fingerprint(), natural.fingerprint());
+        let a: Vec<u64> = natural.gen_iter().take(8).collect();
+        let b: Vec<u64> = resumed.gen_iter().take(8).collect();
+        assert_eq!(a, b);
+
+        resumed.reseed_with_position(3, 4, &[], 0);
+        assert_eq!(resumed.fingerprint(), SipRng::new(3, 4).fingerprint());
+    }
+
+
+    #[test]
+    fn test_advance_many() {
+        for &k in &[0, 1, 2, 17, 1000] {
+            let mut skipped = gen_siprng();
+            let mut serial = skipped.clone();
+            assert_eq!(skipped.next_u32(), serial.next_u32());
+            skipped.advance_many(k);
+            for _ in 0..k {
+                serial.next_u64();
+            }
+            assert_eq!(skipped.next_u64(), serial.next_u64());
+            assert_eq!(skipped.next_u32(), serial.next_u32());
+            assert_eq!(skipped.next_u32(), serial.next_u32());
+        }
+
+        // Across counter overflows.
+        let mut skipped = gen_siprng();
+        let mut serial = skipped.clone();
+        skipped.advance_to(u32::MAX - 2);
+        serial.advance_to(u32::MAX - 2);
+        assert_eq!(skipped.next_u32(), serial.next_u32());
+        skipped.advance_many(5);
+        for _ in 0..5 {
+            serial.next_u64();
+        }
+        assert_eq!(skipped.fingerprint(), serial.fingerprint());
+
+        let mut far = gen_siprng();
+        let mut manual = far.clone();
+        far.advance_many(3 << 32);
+        for _ in 0..3 {
+            manual.advance_to(u32::MAX);
+            manual.next_u64();
+        }
+        assert_eq!(far.depth(), 3);
+        assert_eq!(far.outputs_consumed(), 0);
+        assert_eq!(far.fingerprint(), manual.fingerprint());
+    }
+
+
+    #[test]
+    fn test_advance_to() {
+        let rng = gen_siprng();
+        let outputs: Vec<u64> = rng.clone().gen_iter().take(1000).collect();
+        for &n in [0, 1, 2, 99, 500, 997, 998].iter() {
+            let mut r = rng.clone();
+            r.advance_to(n as u32);
+            assert_eq!(r.next_u64(), outputs[n]);
+            assert_eq!(r.next_u64(), outputs[n + 1]);
+        }
+    }
+
+
+    #[test]
+    fn test_prf() {
+        use ::{SplitRng, SplitPrf};
+
+        let mut rng = gen_siprng();
+        let mut copy = rng.clone();
+        let prf = rng.prf();
+        assert_eq!(rng.next_u64(), copy.next_u64());
+
+        let prf2 = rng.prf();
+        for i in 0..100 {
+            let mut child = rng.clone();
+            child.descend(i);
+            assert_eq!(prf2.call(i).next_u64(), child.next_u64());
+            assert!(prf.call(i).next_u64() != prf2.call(i).next_u64());
+        }
+
+        let mut split = rng.split();
+        assert_eq!(prf2.call(0).next_u64(), rng.next_u64());
+        assert_eq!(prf2.call(1).next_u64(), split.next_u64());
+    }
+
+
+    #[test]
+    fn test_outputs_consumed() {
+        let mut rng = gen_siprng();
+        assert_eq!(rng.outputs_consumed(), 0);
+        for n in 1..100 {
+            rng.next_u64();
+            assert_eq!(rng.outputs_consumed(), n);
+        }
+
+        let mut rng = gen_siprng();
+        let _: (u32, u32) = rng.gen();
+        assert_eq!(rng.outputs_consumed(), 1);
+        let _: [u64; 4] = rng.gen();
+        assert_eq!(rng.outputs_consumed(), 5);
+    }
+
+
+    #[test]
+    fn test_breadth_first() {
+        use ::{SplitRng, SplitPrf};
+
+        let prf = gen_siprng().splitn();
+        let nodes: Vec<SipRng> = prf.breadth_first(3).take(3 + 9 + 27).collect();
+        let mut paths: Vec<Vec<u32>> = Vec::new();
+        for depth in 1..4 {
+            let mut level = vec![vec![]];
+            for _ in 0..depth {
+                level = level.iter()
+                    .flat_map(|p: &Vec<u32>| (0..3).map(move |i| {
+                        let mut q = p.clone();
+                        q.push(i);
+                        q
+                    }))
+                    .collect();
+            }
+            paths.extend(level);
+        }
+        for (node, path) in nodes.iter().zip(paths.iter()) {
+            assert_eq!(node.fingerprint(), prf.call_path(path).fingerprint());
+        }
+        for i in 0..3 {
+            assert_eq!(nodes[i as usize].fingerprint(), prf.call(i).fingerprint());
+        }
+        assert_eq!(prf.call_path(&[2, 1]).fingerprint(),
+                   prf.call(2).prf().call(1).fingerprint());
+        assert_eq!(prf.breadth_first(0).count(), 0);
+        assert_eq!(prf.breadth_first(1).nth(5).unwrap().fingerprint(),
+                   prf.call_path(&[0; 6]).fingerprint());
+    }
+
+
+    #[test]
+    fn test_sequential_splits() {
+        use std::collections::HashSet;
+
+        let (k0, k1) = gen_seed();
+        let mut ra = SipRng::new(k0, k1);
+        let mut rb = SipRng::new(k0, k1);
+        let xs: Vec<u64> = ra.sequential_splits().take(100)
+            .map(|mut r| r.next_u64()).collect();
+        let ys: Vec<u64> = rb.sequential_splits().take(100)
+            .map(|mut r| r.next_u64()).collect();
+        assert_eq!(xs, ys);
+
+        let zs: Vec<u64> = ra.sequential_splits().take(100)
+            .map(|mut r| r.next_u64()).collect();
+        let distinct: HashSet<u64> = xs.iter().chain(zs.iter()).cloned().collect();
+        assert_eq!(distinct.len(), 200);
+
+        let mut splits = ra.sequential_splits();
+        splits.next = Some(u32::MAX);
+        assert!(splits.next().is_some());
+        assert!(splits.next().is_none());
+    }
+
+
+    #[test]
+    fn test_reset_counter() {
+        let mut rng = gen_siprng();
+        let first: Vec<u64> = rng.gen_iter().take(10).collect();
+        rng.reset_counter();
+        let second: Vec<u64> = rng.gen_iter().take(10).collect();
+        assert_eq!(first, second);
+    }
+
+
+    #[test]
+    fn test_root_key() {
+        let (k0, k1) = gen_seed();
+        let mut rng = SipRng::new(k0, k1);
+        assert_eq!(rng.root_key(), Some((k0, k1)));
+        rng.next_u64();
+        assert_eq!(rng.root_key(), Some((k0, k1)));
+
+        let mut child = rng.clone();
+        child.descend(3);
+        assert_eq!(child.root_key(), None);
+        assert_eq!(rng.with_context(0).root_key(), None);
+
+        for i in 0..300 {
+            child.descend(i);
+        }
+        assert_eq!(child.depth(), 301);
+        assert_eq!(child.root_key(), None);
+    }
+
+
+    #[test]
+    fn test_from_rng() {
+        let (k0, k1) = gen_seed();
+        let mut ra = SipRng::from_rng(&mut SipRng::new(k0, k1));
+        let mut rb = SipRng::from_rng(&mut SipRng::new(k0, k1));
+        assert!(::tests::iter_eq(ra.gen_iter::<u64>().take(100),
+                                 rb.gen_iter::<u64>().take(100)));
+    }
+
+
+    #[test]
+    fn test_peek() {
+        let mut rng = gen_siprng();
+        for _ in 0..100 {
+            let a = rng.peek();
+            let b = rng.peek();
+            assert_eq!(a, b);
+            assert_eq!(a, rng.next_u64());
+        }
+    }
+
+
+    #[test]
+    fn test_try_from_entropy() {
+        let mut rng = SipRng::try_from_entropy().expect("no entropy");
+        let mut bytes = [0u8; 32];
+        assert!(rng.try_fill_bytes(&mut bytes).is_ok());
+    }
+
+    #[test]
+    fn test_try_from_reader() {
+        use std::io::{self, Read};
+        use super::SipRngError;
+
+        struct FailingSource;
+
+        impl Read for FailingSource {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("no entropy"))
+            }
+        }
+
+        match SipRng::try_from_reader(&mut FailingSource) {
+            Err(SipRngError::Entropy(ref err))
+                if err.kind() == io::ErrorKind::Other => (),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        let seed: Vec<u8> = (1..17).collect();
+        let mut ra = SipRng::try_from_reader(&mut &seed[..]).unwrap();
+        let mut rb = SipRng::new(0x0807_0605_0403_0201, 0x100f_0e0d_0c0b_0a09);
+        assert_eq!(ra.next_u64(), rb.next_u64());
+    }
+
+
+    #[test]
+    fn test_next_u32_halves() {
+        let rng = gen_siprng();
+        let mut ra = rng.clone();
+        let mut rb = rng.clone();
+        for _ in 0..100 {
+            let lo = ra.next_u32() as u64;
+            let hi = ra.next_u32() as u64;
+            assert_eq!(hi << 32 | lo, rb.next_u64());
+        }
+    }
+
+
+    #[test]
+    fn test_split_thread_rng() {
+        use super::split_thread_rng;
+
+        let mut ra = split_thread_rng();
+        let mut rb = split_thread_rng();
+        let x = ra.rng.borrow().peek();
+        assert_eq!(rb.next_u64(), x);
+        assert!(ra.next_u64() != x);
+
+        let mut ca = ra.split();
+        let mut cb = rb.split();
+        assert!(ca.next_u64() != cb.next_u64());
+        ::tests::test_split_rand_independence(&mut ca);
+    }
+
+
+    fn gen_seed() -> (u64, u64) {
+        let mut osrng = OsRng::new().ok().expect("Could not create OsRng");
+        osrng.gen()
+    }
+
+    #[test]
+    fn test_rng_rand_seeded() {
+        let seed = gen_seed();
+        ::tests::test_rng_rand_seeded::<SipRng, (u64, u64)>(seed);
+    }
+
+    #[test]
+    fn test_rng_seeded() {
+        let seed = gen_seed();
+        ::tests::test_rng_seeded::<SipRng, (u64, u64)>(seed);
+    }
+
+    #[test]
+    fn test_rng_reseed() {
+        let seed = gen_seed();
+        ::tests::test_rng_reseed::<SipRng, (u64, u64)>(seed);
+    }
+
+    #[test]
+    fn test_rng_seeded_u64() {
+        let seed = gen_seed().0;
+        ::tests::test_rng_seeded::<SipRng, u64>(seed);
+    }
+
+    #[test]
+    fn test_rng_reseed_u64() {
+        let seed = gen_seed().0;
+        ::tests::test_rng_reseed::<SipRng, u64>(seed);
+    }
+
+    #[test]
+    fn test_from_u64_diverges() {
+        let mut r0 = SipRng::from_u64(0);
+        let mut r1 = SipRng::from_u64(1);
+        assert!(r0.next_u64() != r1.next_u64());
     }
 
 }
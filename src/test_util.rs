@@ -0,0 +1,112 @@
+//! Assertions for testing `SplitRand` impls, and probes of the
+//! generators' mixing, with the `test-util` feature.
+//!
+//! Call them from the tests of a crate that implements `SplitRand`
+//! for its own types, with the feature enabled in its
+//! `dev-dependencies`:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! rand-split = { version = "0.5", features = ["test-util"] }
+//! ```
+//!
+//! ```ignore
+//! #[test]
+//! fn my_type_is_independent() {
+//!     rand_split::test_util::assert_independent_positions::<MyType>((1, 2), 100);
+//! }
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use siprng::SipRng;
+use super::{SplitRand, SplitRng, SplitPrf};
+
+
+/// Assert that a `T` generated as the second element of a pair is the
+/// same whatever the first element is, and so however much randomness
+/// generating the first element consumed, for `samples` generators
+/// keyed from `seed`.  This holds for any `T` whose impl is
+/// deterministic and draws only from the generator it's given; it
+/// fails, for instance, for one that draws from a thread-local
+/// generator or from a global counter.
+pub fn assert_independent_positions<T>(seed: (u64, u64), samples: u32)
+    where T: SplitRand + PartialEq + Debug
+{
+    let prf = SipRng::new(seed.0, seed.1).splitn();
+    for i in 0..samples {
+        let (_, a): ((), T) = prf.call(i).split_gen();
+        let (_, b): (u8, T) = prf.call(i).split_gen();
+        let (_, c): ([u64; 32], T) = prf.call(i).split_gen();
+        let (_, d): (Vec<String>, T) = prf.call(i).split_gen();
+        let (_, e): (T, T) = prf.call(i).split_gen();
+        assert_eq!(a, b, "second element depends on the first (sample {})", i);
+        assert_eq!(a, c, "second element depends on the first (sample {})", i);
+        assert_eq!(a, d, "second element depends on the first (sample {})", i);
+        assert_eq!(a, e, "second element depends on the first (sample {})", i);
+    }
+}
+
+
+/// Count the collisions among the children of `parent` at the branch
+/// numbers in `indices`: the number of distinct indices whose child's
+/// `fingerprint` equals that of a child at an earlier index.  This is
+/// zero unless descending mixes poorly.  The children are those of
+/// `parent.prf()`, so `parent` isn't advanced.
+pub fn collision_probe(parent: &SipRng, indices: &[u32]) -> usize {
+    let prf = parent.prf();
+    let distinct: HashSet<u32> = indices.iter().cloned().collect();
+    let mut fingerprints = HashSet::with_capacity(distinct.len());
+    distinct.iter()
+        .filter(|&&i| !fingerprints.insert(prf.call(i).fingerprint()))
+        .count()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use rand::Rng;
+    use siprng::SipRng;
+    use super::{assert_independent_positions, collision_probe};
+    use ::{SplitRand, SplitRng};
+
+
+    #[test]
+    fn test_tuples() {
+        assert_independent_positions::<(u32, Vec<u8>)>((1, 2), 100);
+        assert_independent_positions::<(String, (bool, [u16; 3]))>((3, 4), 100);
+    }
+
+    thread_local!(static CALLS: Cell<u64> = const { Cell::new(0) });
+
+    /// A value that depends on how many were generated before it.
+    #[derive(Debug, PartialEq)]
+    struct Counted(u64);
+
+    impl SplitRand for Counted {
+        fn split_rand<R: SplitRng>(rng: &mut R) -> Counted {
+            let n = CALLS.with(|calls| {
+                calls.set(calls.get() + 1);
+                calls.get()
+            });
+            Counted(rng.next_u64() ^ n)
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_dependent() {
+        assert_independent_positions::<Counted>((5, 6), 10);
+    }
+
+    #[test]
+    fn test_collision_probe() {
+        let mut rng = SipRng::new(7, 8);
+        let indices: Vec<u32> = (0..100_000).map(|_| rng.gen()).collect();
+        assert_eq!(collision_probe(&rng, &indices), 0);
+        assert_eq!(collision_probe(&rng, &[3, 3, 3]), 0);
+        assert_eq!(collision_probe(&rng, &[]), 0);
+    }
+
+}
@@ -150,11 +150,141 @@ mod tests {
         ::tests::test_split_rand_closure(&mut gen_twolcg());
     }
 
+    #[test]
+    fn test_split_rand_fallible_closure() {
+        ::tests::test_split_rand_fallible_closure(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_rand_closure_threads() {
+        ::tests::test_split_rand_closure_threads(&mut gen_twolcg());
+    }
+
     #[test]
     fn test_split_rand_split() {
         ::tests::test_split_rand_split(&mut gen_twolcg());
     }
 
+    #[test]
+    fn test_split_map() {
+        ::tests::test_split_map(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split2() {
+        ::tests::test_split2(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_sample_iter() {
+        ::tests::test_split_sample_iter(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_permutation() {
+        ::tests::test_split_permutation(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_assoc_list() {
+        ::tests::test_split_assoc_list(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_partition() {
+        ::tests::test_split_partition(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_table() {
+        ::tests::test_split_table(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_many() {
+        ::tests::test_split_many(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_zip_gen() {
+        ::tests::test_zip_gen(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_gen_timestamps() {
+        ::tests::test_split_gen_timestamps(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_grid() {
+        ::tests::test_split_grid(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_map_filter_gen() {
+        ::tests::test_split_map_filter_gen(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_map_exact() {
+        ::tests::test_split_map_exact(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_markov_step() {
+        ::tests::test_split_markov_step(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_build_hasher() {
+        ::tests::test_split_build_hasher(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_rand_closure_tuple() {
+        ::tests::test_split_rand_closure_tuple(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_fold() {
+        ::tests::test_split_fold(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_generic_consumer() {
+        ::tests::test_generic_consumer(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_gen_bitset() {
+        ::tests::test_split_gen_bitset(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_gen_subset() {
+        ::tests::test_split_gen_subset(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_gen_uuid() {
+        ::tests::test_split_gen_uuid(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_gen_normal() {
+        ::tests::test_split_gen_normal(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_gen_bools() {
+        ::tests::test_split_gen_bools(&mut gen_twolcg());
+    }
+
+    #[test]
+    fn test_split_gen_entry_points() {
+        ::tests::test_split_gen_entry_points(&mut gen_twolcg());
+    }
+
 
     fn gen_seed() -> [u64; 4] {
         let mut osrng = OsRng::new().ok().expect("Could not create OsRng");
@@ -0,0 +1,113 @@
+//! Golden-output tests.  These pin down the exact output of `SipRng`
+//! for a few seeds and split paths, so that a change to the output
+//! can't slip in unnoticed.  If they fail, the change is
+//! incompatible for everybody who relies on reproducing streams from
+//! their seeds: either undo it, or bump the crate's major version and
+//! regenerate the expected values below.
+
+extern crate rand;
+extern crate rand_split;
+
+use rand::Rng;
+use rand_split::{SplitRng, SplitPrf};
+use rand_split::siprng::SipRng;
+
+
+const SEEDS: [(u64, u64); 3] = [
+    (0, 0),
+    (0x0706050403020100, 0x0f0e0d0c0b0a0908),
+    (0xdead_beef_cafe_f00d, 0x0123_4567_89ab_cdef),
+];
+
+/// A step along a path of splits.
+enum Step {
+    /// Keep the parent after a `split`.
+    Parent,
+    /// Take the child returned by a `split`.
+    Child,
+    /// Take branch `i` of a PRF split off the generator.
+    Call(u32),
+    /// Draw an output and throw it away.
+    Skip,
+}
+
+use Step::*;
+
+const PATHS: [&[Step]; 6] = [
+    &[],
+    &[Parent],
+    &[Child],
+    &[Call(7)],
+    &[Skip, Child, Call(0), Parent],
+    &[Call(0xffff_ffff), Child, Child, Skip, Skip, Call(1)],
+];
+
+fn follow(seed: (u64, u64), path: &[Step]) -> SipRng {
+    let mut rng = SipRng::new(seed.0, seed.1);
+    for step in path {
+        rng = match *step {
+            Parent => { rng.split(); rng }
+            Child => rng.split(),
+            Call(i) => rng.splitn().call(i),
+            Skip => { rng.next_u64(); rng }
+        };
+    }
+    rng
+}
+
+/// The first four outputs for each seed (rows) and path (columns).
+const GOLDEN: [[[u64; 4]; 6]; 3] = [
+    [
+        [0x32caecc280172976, 0xc043a198a8f05a5a,
+         0x0c3eb2268943c1dd, 0x823e36166e772596],
+        [0xc8f0c3a88fd17f76, 0xaecdc222ca687717,
+         0xf96ca853b91eada1, 0x0af7c8de645cf910],
+        [0x73b1466fcb1bf0c6, 0x4ea115d1e603b5e7,
+         0x56729db4e8e02ef6, 0xfbffdc7ea81357ae],
+        [0x4d5a0578f24d9a21, 0xfc44e49c96ea6de3,
+         0xcdb0ba88b54c431c, 0xc17108e94452e11c],
+        [0x995344125ee207e6, 0xbb8b9c0f0aac6cb8,
+         0x5853a5cfc23b5d09, 0x922c0719f2e1e341],
+        [0xa54568af298fa46d, 0xa824ee806423e5a2,
+         0x8846386521a74c40, 0x8eb4155c06d81460],
+    ],
+    [
+        [0x98a0d3c0ef557701, 0x8b62d01d7ce96e82,
+         0xaa5b86ac7e02bfae, 0x1fa4ef64da922607],
+        [0xb1f75064c6cec9d8, 0x960f6ffc5d3e9dfa,
+         0x54517882d2d2e612, 0x7f3347d001760a09],
+        [0x9e586bb4b5272319, 0xa077be14a3ab0754,
+         0xa1b31b842a597404, 0x35a7f78a8485dbc4],
+        [0x416b25ec182a408a, 0x8c0a67c226ccdc14,
+         0x2db3195137df7b65, 0x646a56645d3e72c6],
+        [0x2ede1d12d5be0254, 0x32e519d2a215b8a1,
+         0xb27ec0bcf9d7abe7, 0x4896da53ab954ff1],
+        [0x1938aa630ce32090, 0x18375352c8969a47,
+         0x5b211ca251ffd536, 0x01dde329c564e3e7],
+    ],
+    [
+        [0x42cd0a7c5a8c25fe, 0x1ff14a93cf5b7cc0,
+         0x5a96779a207f183a, 0x8b1175df66293a7e],
+        [0x644f4d9a182ae6d9, 0x462a98eb35af58d6,
+         0x9782bd811f6f4eff, 0x78f3060394b9891b],
+        [0x795281917bd46b54, 0xbcd2ebe9a8c10e2c,
+         0x3ffc25199688aa66, 0xb22c23dbbe8600c2],
+        [0x0e7c4b1598827606, 0xaa49910d9bffffe5,
+         0x427bcfbf3d0b358f, 0xb9332aefcec31435],
+        [0xed81c52e369f1fad, 0xeaf56fd468cf827b,
+         0x20412911c4ed399a, 0xb570c14f81cb06f9],
+        [0x57aa20cd9c03ef24, 0xb2bc6e371972e5ac,
+         0x2c9487b422119085, 0x65b1da9f34259e28],
+    ],
+];
+
+#[test]
+fn test_golden() {
+    for (s, &seed) in SEEDS.iter().enumerate() {
+        for (p, path) in PATHS.iter().enumerate() {
+            let mut rng = follow(seed, path);
+            let outputs: Vec<u64> = rng.gen_iter().take(4).collect();
+            assert_eq!(outputs, GOLDEN[s][p], "seed {}, path {}", s, p);
+        }
+    }
+}
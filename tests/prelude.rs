@@ -0,0 +1,40 @@
+//! Typical use, with nothing but the prelude imported.
+
+extern crate rand_split;
+
+use rand_split::prelude::*;
+
+
+struct Job {
+    id:       u32,
+    priority: Seq<u8>,
+}
+
+impl SplitRand for Job {
+    fn split_rand<R: SplitRng>(rng: &mut R) -> Job {
+        let prf = rng.splitn();
+        Job {
+            id:       prf.call(0).split_gen(),
+            priority: prf.call(1).split_gen(),
+        }
+    }
+}
+
+#[test]
+fn test_prelude() {
+    let mut a = SipRng::from_seed(42u64);
+    let mut b = SipRng::from_seed(42u64);
+
+    let (ja, jb): (Job, Job) = (a.split_gen(), split_rand(&mut b));
+    assert_eq!(ja.id, jb.id);
+    assert_eq!(ja.priority.0, jb.priority.0);
+
+    let (mut ca, mut cb) = (a.split(), b.split());
+    assert_eq!(ca.next_u64(), cb.next_u64());
+    assert_eq!(ca.gen::<f64>(), cb.gen::<f64>());
+
+    let (pa, pb) = (a.splitn(), b.splitn());
+    let xs: Vec<u16> = (0..10).map(|i| pa.call(i).split_gen()).collect();
+    let ys: Vec<u16> = (0..10).map(|i| pb.call(i).split_gen()).collect();
+    assert_eq!(xs, ys);
+}
@@ -0,0 +1,24 @@
+//! Tests of the low-level `SipRng` API of the `unstable` feature.
+
+#![cfg(feature = "unstable")]
+
+extern crate rand;
+extern crate rand_split;
+
+use rand::Rng;
+use rand_split::SplitPrf;
+use rand_split::siprng::SipRng;
+
+
+#[test]
+fn test_descend_advance() {
+    let rng = SipRng::new(11, 12);
+    let prf = rng.prf();
+    for i in 0..100 {
+        let mut called = prf.call(i);
+        let mut manual = SipRng::new(11, 12);
+        manual.descend(i);
+        assert_eq!(manual.advance(), called.next_u64());
+        assert_eq!(manual.advance(), called.next_u64());
+    }
+}